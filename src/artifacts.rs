@@ -0,0 +1,383 @@
+//! Output of every report as separate files in a directory.
+
+use crate::display;
+use crate::explain;
+use crate::html;
+use crate::hungarian;
+use crate::loaders::Cutoff;
+use crate::model::{Assignments, ProjectId, Student};
+use crate::report;
+use eyre::Context;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Write the artifacts into `dir`, which is created if needed:
+///
+/// - `report.txt`: the plain-text report;
+/// - `report.html`: the HTML report;
+/// - `explanations.txt`: why every student did not get a better ranked
+///   project;
+/// - `assignments.csv`: the assignments of all students, in the configured
+///   CSV format;
+/// - `projects/NAME.csv`: the roster of every open project;
+/// - `rosters/NAME.csv`: the roster of every occurrence of the open
+///   projects, with contact details, suffixed by the occurrence number for
+///   projects with several occurrences;
+/// - `unassigned.csv`: the unassigned and unregistered students;
+/// - `missed_bonuses.csv`: the students who did not benefit from a bonus.
+///
+/// `dropped` are the unregistered students which have been dropped from
+/// the system and are not part of the assignments, and `cutoff` the cutoff
+/// applied to the preferences and bonuses, if any.
+pub fn write_artifacts(
+    dir: &Path,
+    a: &Assignments,
+    dropped: &[Student],
+    cutoff: Option<&Cutoff>,
+    config: &hungarian::Config,
+    csv: &display::CsvConfig,
+    rename_lazy: bool,
+) -> eyre::Result<()> {
+    let projects_dir = dir.join("projects");
+    let rosters_dir = dir.join("rosters");
+    for d in [&projects_dir, &rosters_dir] {
+        std::fs::create_dir_all(d).context(format!("cannot create directory `{}`", d.display()))?;
+    }
+    write_file(&dir.join("report.txt"), |out| {
+        Ok(display::display_report(
+            out,
+            a,
+            rename_lazy,
+            dropped.len(),
+            cutoff,
+        )?)
+    })?;
+    write_html(
+        &dir.join("report.html"),
+        a,
+        rename_lazy,
+        dropped.len(),
+        cutoff,
+    )?;
+    write_file(&dir.join("explanations.txt"), |out| {
+        Ok(display::display_explanations(
+            out,
+            &explain::explanations(a, config),
+        )?)
+    })?;
+    write_file(&dir.join("assignments.csv"), |out| {
+        display::display_csv(out, a, csv)
+    })?;
+    let mut projects = a.filter_projects(|p| a.is_open(p));
+    projects.sort_by_key(|&p| a.project(p).name.clone());
+    let mut file_names = HashSet::new();
+    for p in projects {
+        let file_name = unique_file_name(&a.project(p).name, &mut file_names);
+        write_file(&projects_dir.join(file_name), |out| write_roster(out, a, p))?;
+    }
+    let mut file_names = HashSet::new();
+    for roster in report::rosters(a) {
+        let name = if roster.occurrences == 1 {
+            roster.project.clone()
+        } else {
+            format!("{} {}", roster.project, roster.occurrence)
+        };
+        let file_name = unique_file_name(&name, &mut file_names);
+        write_file(&rosters_dir.join(file_name), |out| {
+            write_occurrence_roster(out, &roster)
+        })?;
+    }
+    write_file(&dir.join("unassigned.csv"), |out| {
+        write_unassigned(out, a, dropped)
+    })?;
+    write_file(&dir.join("missed_bonuses.csv"), |out| {
+        let mut wtr = csv::Writer::from_writer(out);
+        wtr.write_record([
+            "name",
+            "login",
+            "project",
+            "rank",
+            "bonus_project",
+            "bonus_rank",
+            "bonus",
+        ])?;
+        for m in report::missed_bonuses(a) {
+            wtr.write_record([
+                m.student.name.as_str(),
+                m.student.login.as_deref().unwrap_or_default(),
+                &m.project,
+                &m.rank.to_string(),
+                &m.bonus_project,
+                &m.bonus_rank.to_string(),
+                &m.bonus.to_string(),
+            ])?;
+        }
+        Ok(())
+    })
+}
+
+/// Write the HTML report into `file_name`.
+pub fn write_html(
+    file_name: &Path,
+    a: &Assignments,
+    rename_lazy: bool,
+    eliminated: usize,
+    cutoff: Option<&Cutoff>,
+) -> eyre::Result<()> {
+    write_file(file_name, |out| {
+        Ok(html::write_html(out, a, rename_lazy, eliminated, cutoff)?)
+    })
+}
+
+/// Create a file and fill it with `f`.
+fn write_file(
+    file_name: &Path,
+    f: impl FnOnce(&mut BufWriter<File>) -> eyre::Result<()>,
+) -> eyre::Result<()> {
+    let mut out = BufWriter::new(
+        File::create(file_name).context(format!("cannot create `{}`", file_name.display()))?,
+    );
+    f(&mut out)
+        .and_then(|()| Ok(out.flush()?))
+        .context(format!("cannot write `{}`", file_name.display()))
+}
+
+/// List the students of a project, with the rank they gave to it.
+fn write_roster(out: impl Write, a: &Assignments, p: ProjectId) -> eyre::Result<()> {
+    let mut wtr = csv::Writer::from_writer(out);
+    wtr.write_record(["last_name", "first_name", "login", "rank"])?;
+    let mut students = a.students_for(p).clone();
+    students.sort_by_key(|&s| {
+        (
+            a.student(s).last_name.clone(),
+            a.student(s).first_name.clone(),
+        )
+    });
+    for s in students {
+        let student = a.student(s);
+        wtr.write_record([
+            student.last_name.as_str(),
+            &student.first_name,
+            student.login.as_deref().unwrap_or_default(),
+            &a.rank_of(s, p)
+                .map(|r| (r + 1).to_string())
+                .unwrap_or_default(),
+        ])?;
+    }
+    Ok(())
+}
+
+/// List the students of an occurrence with their contact details.
+fn write_occurrence_roster(out: impl Write, roster: &report::Roster) -> eyre::Result<()> {
+    let mut wtr = csv::Writer::from_writer(out);
+    wtr.write_record([
+        "project",
+        "occurrence",
+        "last_name",
+        "first_name",
+        "login",
+        "email",
+        "rank",
+        "pinned",
+        "unregistered",
+    ])?;
+    let occurrence = roster.occurrence.to_string();
+    for s in &roster.students {
+        wtr.write_record([
+            roster.project.as_str(),
+            &occurrence,
+            &s.last_name,
+            &s.first_name,
+            s.login.as_deref().unwrap_or_default(),
+            s.email.as_deref().unwrap_or_default(),
+            &s.rank.map(|r| r.to_string()).unwrap_or_default(),
+            if s.pinned { "yes" } else { "no" },
+            if s.unregistered { "yes" } else { "no" },
+        ])?;
+    }
+    Ok(())
+}
+
+/// List the unassigned students, then the unregistered ones, along with the
+/// project they have been given if any.
+fn write_unassigned(out: impl Write, a: &Assignments, dropped: &[Student]) -> eyre::Result<()> {
+    let mut wtr = csv::Writer::from_writer(out);
+    wtr.write_record(["last_name", "first_name", "login", "status", "project"])?;
+    let mut students = a
+        .filter_students(|s| a.project_for(s).is_none() && !a.is_lazy(s))
+        .into_iter()
+        .map(|s| (a.student(s), "unassigned", None))
+        .collect::<Vec<_>>();
+    let mut unregistered = a
+        .filter_students(|s| a.is_lazy(s))
+        .into_iter()
+        .map(|s| (a.student(s), "unregistered", a.project_for(s)))
+        .chain(dropped.iter().map(|s| (s, "dropped", None)))
+        .collect::<Vec<_>>();
+    for list in [&mut students, &mut unregistered] {
+        list.sort_by_key(|(s, _, _)| (s.last_name.clone(), s.first_name.clone()));
+    }
+    for (student, status, project) in students.into_iter().chain(unregistered) {
+        wtr.write_record([
+            student.last_name.as_str(),
+            &student.first_name,
+            student.login.as_deref().unwrap_or_default(),
+            status,
+            project.map_or("", |p| a.project(p).name.as_str()),
+        ])?;
+    }
+    Ok(())
+}
+
+/// File name of the roster of a project, made of the alphanumeric
+/// characters of its name, which is not part of `used` yet.
+fn unique_file_name(name: &str, used: &mut HashSet<String>) -> String {
+    let base = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    let base = if base.is_empty() {
+        String::from("project")
+    } else {
+        base
+    };
+    let mut file_name = format!("{base}.csv");
+    let mut n = 1;
+    while !used.insert(file_name.clone()) {
+        n += 1;
+        file_name = format!("{base}-{n}.csv");
+    }
+    file_name
+}
+
+#[test]
+fn test_unique_file_name() {
+    let mut used = HashSet::new();
+    assert_eq!(
+        unique_file_name("Vidéo stéréoscopique 360° sur FPGA", &mut used),
+        "vidéo-stéréoscopique-360-sur-fpga.csv"
+    );
+    assert_eq!(unique_file_name("A/B", &mut used), "a-b.csv");
+    assert_eq!(unique_file_name("a b", &mut used), "a-b-2.csv");
+    assert_eq!(unique_file_name("???", &mut used), "project.csv");
+}
+
+/// Assignments where a student got their project and another one none,
+/// along with an unregistered student if `with_lazy` is set.
+#[cfg(test)]
+fn sample_assignments(with_lazy: bool) -> Assignments {
+    use crate::model::StudentId;
+    use crate::testing;
+    let projects = ["Robots", "Drones"]
+        .into_iter()
+        .enumerate()
+        .map(|(n, name)| testing::project(n, name, 1, 2))
+        .collect();
+    let mut students = vec![
+        Student {
+            email: Some("ada@example.com".into()),
+            ..sample_student(0, "Ada", "Lovelace", &[0])
+        },
+        sample_student(1, "Grace", "Hopper", &[1]),
+    ];
+    if with_lazy {
+        students.push(sample_student(2, "Alan", "Turing", &[]));
+    }
+    let mut a = Assignments::new(students, projects);
+    a.assign_to(StudentId(0), ProjectId(0));
+    if with_lazy {
+        a.assign_to(StudentId(2), ProjectId(0));
+    }
+    a
+}
+
+/// A student with a login made of the initial and the last name.
+#[cfg(test)]
+fn sample_student(n: usize, first: &str, last: &str, rankings: &[usize]) -> Student {
+    Student {
+        login: Some(format!("{}{}", &first[..1], last).to_lowercase()),
+        ..crate::testing::student(n, first, last, rankings)
+    }
+}
+
+#[test]
+fn test_write_artifacts() {
+    let a = sample_assignments(true);
+    let dir = std::env::temp_dir().join(format!("rsolver-artifacts-{}", std::process::id()));
+    let cutoff = Cutoff {
+        as_of: "2018-06-10".parse().unwrap(),
+        late_preferences: 2,
+        late_bonuses: 1,
+    };
+    write_artifacts(
+        &dir,
+        &a,
+        &[],
+        Some(&cutoff),
+        &hungarian::Config::default(),
+        &display::CsvConfig::default(),
+        false,
+    )
+    .unwrap();
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    let files = [
+        "report.txt",
+        "report.html",
+        "explanations.txt",
+        "assignments.csv",
+        "missed_bonuses.csv",
+    ]
+    .map(|name| dir.join(name).is_file());
+    let (report, unassigned, roster, occurrence_roster) = (
+        read("report.txt"),
+        read("unassigned.csv"),
+        read("projects/robots.csv"),
+        read("rosters/robots.csv"),
+    );
+    let drones = dir.join("projects/drones.csv").exists();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(files, [true; 5]);
+    assert!(!drones);
+    assert!(report.contains("Ignored after 2018-06-10 23:59:59: 2 preferences, 1 bonuses\n"));
+    assert_eq!(
+        unassigned,
+        "last_name,first_name,login,status,project\n\
+         Hopper,Grace,ghopper,unassigned,\n\
+         Turing,Alan,aturing,unregistered,Robots\n"
+    );
+    assert_eq!(
+        roster,
+        "last_name,first_name,login,rank\n\
+         Lovelace,Ada,alovelace,1\n\
+         Turing,Alan,aturing,\n"
+    );
+    assert_eq!(
+        occurrence_roster,
+        "project,occurrence,last_name,first_name,login,email,rank,pinned,unregistered\n\
+         Robots,1,Lovelace,Ada,alovelace,ada@example.com,1,no,no\n\
+         Robots,1,Turing,Alan,aturing,,,no,yes\n"
+    );
+}
+
+#[test]
+fn test_write_unassigned() {
+    // Unregistered students dropped from the system are not part of the
+    // assignments
+    let a = sample_assignments(false);
+    let dropped = [Student {
+        login: None,
+        ..sample_student(2, "Alan", "Turing", &[])
+    }];
+    let mut out = Vec::new();
+    write_unassigned(&mut out, &a, &dropped).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "last_name,first_name,login,status,project\n\
+         Hopper,Grace,ghopper,unassigned,\n\
+         Turing,Alan,,dropped,\n"
+    );
+}
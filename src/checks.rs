@@ -4,15 +4,18 @@ use tracing::warn;
 
 pub fn check_pinned_consistency(a: &Assignments) {
     for s in a.all_students() {
-        if let Some(p) = a.rankings(s).first()
-            && a.is_pinned_for(s, *p)
-            && a.project_for(s) != Some(*p)
-        {
-            warn!(
-                student = %a.student(s),
-                project = %a.project(*p),
-                "student did not get pinned project"
-            );
+        let first = a.projects_at_rank(s, 0);
+        if a.project_for(s).is_some_and(|p| first.contains(&p)) {
+            continue;
+        }
+        for &p in first {
+            if a.is_pinned_for(s, p) {
+                warn!(
+                    student = %a.student(s),
+                    project = %a.project(p),
+                    "student did not get pinned project"
+                );
+            }
         }
     }
 }
@@ -1,213 +1,568 @@
 #![allow(clippy::module_name_repetitions)]
 
-use crate::model::{Assignments, StudentId};
-use crate::stats;
+use crate::explain::Explanation;
+use crate::loaders::{Cutoff, HistoryRun};
+use crate::model::{Assignments, Project, ProjectId, Student, StudentId};
+use crate::report::{self, Report};
+use crate::solver::Change;
+use clap::ValueEnum;
+use eyre::{OptionExt, bail};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Write;
 
-pub fn display_details(a: &Assignments, rename_lazy: bool) {
-    let mut projects = a.filter_projects(|p| a.is_open(p));
-    projects.sort_by_key(|&p| a.project(p).name.clone());
-    for p in projects {
-        let mut lazy_index = 0;
-        let mut students = a
-            .students_for(p)
-            .iter()
-            .map(|&s| {
-                (
-                    if rename_lazy && a.student(s).is_lazy() {
-                        lazy_index += 1;
-                        format!("Zzz {lazy_index}")
-                    } else {
-                        a.student(s).name.clone()
-                    },
-                    s,
-                )
-            })
-            .collect::<Vec<_>>();
-        students.sort_by_key(|(name, _)| name.clone());
-        if !students.is_empty() {
-            if a.max_occurrences(p) == 1 {
-                println!("{proj}:", proj = a.project(p));
-            } else {
-                println!(
-                    "{proj} ({occ} occurrences):",
-                    proj = a.project(p),
-                    occ = a.max_occurrences(p)
-                );
+pub fn display_details(
+    out: &mut impl Write,
+    a: &Assignments,
+    rename_lazy: bool,
+) -> std::io::Result<()> {
+    for project in report::details(a, rename_lazy) {
+        if project.occurrences == 1 {
+            writeln!(out, "{proj}:", proj = project.name)?;
+        } else {
+            writeln!(
+                out,
+                "{proj} ({occ} occurrences):",
+                proj = project.name,
+                occ = project.occurrences
+            )?;
+        }
+        let info = &project.info;
+        if let Some(contact) = info.supervisor_contact() {
+            writeln!(out, "  Supervisor: {contact}")?;
+        }
+        if let Some(url) = &info.url {
+            writeln!(out, "  URL: {url}")?;
+        }
+        if let Some(description) = &info.description {
+            writeln!(out, "  Description: {description}")?;
+        }
+        for s in project.students {
+            write!(out, "  - {student}", student = s.student)?;
+            if let Some(rank) = s.rank {
+                write!(out, " (rank {rank})")?;
             }
-            for (name, s) in students {
-                print!("  - {name}");
-                if let Some(rank) = a.rank_of(s, p) {
-                    print!(" (rank {r})", r = rank + 1);
-                }
-                if a.is_pinned_and_has_chosen(s, p) {
-                    print!(" (pinned)");
+            if s.pinned {
+                write!(out, " (pinned)")?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Display the assignment of the students with the given logins. Students
+/// which are not part of the assignments have been dropped as unregistered.
+pub fn display_students(a: &Assignments, logins: &[String]) {
+    for result in report::student_results(a, logins) {
+        let Some(name) = result.name else {
+            println!("{login}: unregistered", login = result.login);
+            continue;
+        };
+        match result.project {
+            Some(project) => {
+                print!("{name} ({login}): {project}", login = result.login);
+                if let Some(rank) = result.rank {
+                    print!(" (rank {rank})");
                 }
                 println!();
             }
-            println!();
+            None => println!("{name} ({login}): unassigned", login = result.login),
         }
     }
 }
 
-#[allow(clippy::cast_precision_loss)]
-pub fn display_stats(a: &Assignments, eliminated: usize) {
-    let students = a.all_students().len();
-    let lazy = (0..students)
-        .filter(|&s| a.rankings(StudentId(s)).is_empty())
-        .count();
-    assert!(
-        lazy == 0 || eliminated == 0,
-        "cannot have lazy students if they have been eliminated"
-    );
-    let (unconsidered_str, unconsidered) = if eliminated > 0 {
-        ("unconsidered", eliminated)
-    } else {
-        ("unregistered", lazy)
-    };
-    println!(
+/// Display the assignment of the students with the given logins as JSON.
+pub fn display_students_json(
+    out: &mut impl Write,
+    a: &Assignments,
+    logins: &[String],
+) -> eyre::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, &report::student_results(a, logins))?;
+    writeln!(out)?;
+    Ok(())
+}
+
+pub fn display_stats(
+    out: &mut impl Write,
+    a: &Assignments,
+    eliminated: usize,
+    cutoff: Option<&Cutoff>,
+) -> std::io::Result<()> {
+    let stats = report::stats(a, eliminated, cutoff);
+    writeln!(
+        out,
         "Students registered/{}/total: {}/{}/{}",
-        unconsidered_str,
-        students - lazy,
-        unconsidered,
-        students + eliminated,
-    );
-    println!(
+        if stats.dropped_unregistered {
+            "unconsidered"
+        } else {
+            "unregistered"
+        },
+        stats.registered,
+        stats.unregistered,
+        stats.total,
+    )?;
+    writeln!(
+        out,
         "Projects/occurrences: {proj}/{occ}",
-        proj = a.filter_projects(|p| a.is_open(p)).len(),
-        occ = a
-            .filter_projects(|p| a.is_open(p))
-            .into_iter()
-            .map(|p| a.max_occurrences(p))
-            .sum::<u32>()
+        proj = stats.projects,
+        occ = stats.occurrences
+    )?;
+    writeln!(out, "Final ranking:")?;
+    for rank in stats.ranks {
+        writeln!(
+            out,
+            "  - rank {r}: {n} (cumulative {c} - {percent:.2}%)",
+            r = rank.rank,
+            n = rank.students,
+            c = rank.cumulative,
+            percent = rank.percent
+        )?;
+    }
+    if let Some(cutoff) = stats.cutoff {
+        writeln!(
+            out,
+            "Ignored after {as_of}: {p} preferences, {b} bonuses",
+            as_of = cutoff.as_of,
+            p = cutoff.late_preferences,
+            b = cutoff.late_bonuses
+        )?;
+    }
+    Ok(())
+}
+
+pub fn display_runs(runs: &[HistoryRun]) {
+    if runs.is_empty() {
+        println!("No run recorded in history");
+    }
+    for run in runs {
+        print!("Run {id} on {date}", id = run.id, date = run.date);
+        if let Some(campaign) = &run.campaign {
+            print!(" for campaign {campaign}");
+        }
+        println!(":");
+        println!("  - parameters: {config}", config = run.config);
+        println!(
+            "  - assigned students: {assigned}/{students}",
+            assigned = run.stats.assigned,
+            students = run.stats.students
+        );
+        println!(
+            "  - ranks: {ranks}",
+            ranks = run
+                .stats
+                .ranks
+                .iter()
+                .enumerate()
+                .filter(|&(_, &n)| n != 0)
+                .map(|(rank, n)| format!("{n} at rank {r}", r = rank + 1))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+}
+
+/// Display the changes from the stored assignments. Students and projects
+/// are looked up by their original ids.
+pub fn display_changes(changes: &[Change], students: &[Student], projects: &[Project]) {
+    if changes.is_empty() {
+        println!("No changes from stored assignments");
+        return;
+    }
+    let students = students
+        .iter()
+        .map(|s| (s.id, s))
+        .collect::<HashMap<_, _>>();
+    let projects = projects
+        .iter()
+        .map(|p| (p.id, p))
+        .collect::<HashMap<_, _>>();
+    let describe = |student: Option<&Student>, project: Option<ProjectId>| {
+        let Some(project) = project else {
+            return String::from("unassigned");
+        };
+        let name = projects
+            .get(&project)
+            .map_or_else(|| format!("project {}", project.0), |p| p.name.clone());
+        match student.and_then(|s| s.rank_of(project)) {
+            Some(rank) => format!("{name} (rank {r})", r = rank + 1),
+            None => name,
+        }
+    };
+    println!(
+        "Changes from stored assignments ({n} student{s}):",
+        n = changes.len(),
+        s = if changes.len() == 1 { "" } else { "s" }
     );
-    let ranks = stats::statistics(a);
-    let cumul = ranks.iter().scan(0, |s, &r| {
-        *s += r;
-        Some(*s)
-    });
-    let total: usize = ranks.iter().sum();
-    println!("Final ranking:");
-    for (rank, (n, c)) in ranks.iter().zip(cumul).enumerate() {
-        if *n != 0 {
-            println!(
-                "  - rank {r}: {n} (cumulative {c} - {percent:.2}%)",
-                r = rank + 1,
-                percent = 100.0 * c as f32 / total as f32
-            );
-        }
-    }
-}
-
-pub fn display_empty(a: &Assignments) {
-    let mut projects = a.filter_projects(|p| !a.is_open(p));
-    projects.sort_by_key(|&p| a.project(p).name.clone());
+    for change in changes {
+        // Students from an old run may not exist anymore
+        let student = students.get(&change.student).copied();
+        println!(
+            "  - {name}: {old} -> {new}",
+            name = student.map_or_else(
+                || format!("student {}", change.student.0),
+                Student::to_string
+            ),
+            old = describe(student, change.old),
+            new = describe(student, change.new)
+        );
+    }
+}
+
+pub fn display_empty(out: &mut impl Write, a: &Assignments) -> std::io::Result<()> {
+    let projects = report::empty(a);
     if !projects.is_empty() {
-        println!("Empty projects:");
-        for p in projects {
-            println!("  - {proj}", proj = a.project(p));
+        writeln!(out, "Empty projects:")?;
+        for project in projects {
+            writeln!(out, "  - {project}")?;
         }
     }
+    Ok(())
 }
 
-pub fn display_with_many_lazy(a: &Assignments) {
-    let mut projects = a
-        .filter_projects(|p| a.is_open(p))
-        .iter()
-        .filter_map(|&p| {
-            let lazy = a.students_for(p).iter().filter(|&&s| a.is_lazy(s)).count();
-            let regular = a.students_for(p).len() - lazy;
-            if lazy >= regular {
-                Some((p, regular, lazy))
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    projects.sort_by_key(|&(p, _, _)| a.project(p).name.clone());
+pub fn display_with_many_lazy(out: &mut impl Write, a: &Assignments) -> std::io::Result<()> {
+    let projects = report::with_many_lazy(a);
     if !projects.is_empty() {
-        println!(
+        writeln!(
+            out,
             "Projects with at least half the members being unregistered students (unregistered/total):"
-        );
-        for (p, regular, lazy) in projects {
-            println!(
+        )?;
+        for p in projects {
+            writeln!(
+                out,
                 "  - {proj} ({lazy}/{all})",
-                proj = a.project(p),
-                all = lazy + regular
-            );
+                proj = p.project,
+                lazy = p.unregistered,
+                all = p.total
+            )?;
         }
     }
+    Ok(())
 }
 
-pub fn display_missed_bonuses(a: &Assignments) {
-    let mut missed_bonuses = a
-        .all_students()
-        .into_iter()
-        .flat_map(|s| {
-            if let Some(p) = a.project_for(s) {
-                if a.bonus(s, p).is_some() {
-                    vec![]
-                } else if let Some(r) = a.rank_of(s, p) {
-                    let mut bonuses = a
-                        .bonuses(s)
-                        .iter()
-                        .filter_map(|(&pp, &b)| {
-                            a.rank_of(s, pp).and_then(|rr| {
-                                if rr < r && b > 0 {
-                                    Some((s, p, r, pp, rr, b))
-                                } else {
-                                    None
-                                }
-                            })
-                        })
-                        .collect::<Vec<_>>();
-                    bonuses.sort_by_key(|&(_s, _p, _r, _pp, rr, _b)| rr);
-                    bonuses
-                } else {
-                    vec![]
-                }
-            } else {
-                vec![]
-            }
-        })
-        .collect::<Vec<_>>();
+pub fn display_missed_bonuses(out: &mut impl Write, a: &Assignments) -> std::io::Result<()> {
+    let missed_bonuses = report::missed_bonuses(a);
     if !missed_bonuses.is_empty() {
-        missed_bonuses.sort_by_key(|&(s, _p, _r, pp, _rr, b)| {
-            (a.project(pp).name.clone(), -b, a.student(s).name.clone())
-        });
-        println!("Useless bonuses:");
-        for (s, p, r, pp, rr, b) in missed_bonuses {
-            println!(
+        writeln!(out, "Useless bonuses:")?;
+        for m in missed_bonuses {
+            writeln!(
+                out,
                 "  - {s} was assigned to {p} (rank {r}) despite having a bonus of {b} for {pp} (rank {rr})",
-                s = a.student(s),
-                p = a.project(p),
-                r = r + 1,
-                pp = a.project(pp),
-                rr = rr + 1
-            );
+                s = m.student,
+                p = m.project,
+                r = m.rank,
+                pp = m.bonus_project,
+                rr = m.bonus_rank,
+                b = m.bonus
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Display the full plain-text report: the assignments, the statistics and
+/// the remarks on the assignments.
+pub fn display_report(
+    out: &mut impl Write,
+    a: &Assignments,
+    rename_lazy: bool,
+    eliminated: usize,
+    cutoff: Option<&Cutoff>,
+) -> std::io::Result<()> {
+    display_details(out, a, rename_lazy)?;
+    display_stats(out, a, eliminated, cutoff)?;
+    display_missed_bonuses(out, a)?;
+    display_empty(out, a)?;
+    display_with_many_lazy(out, a)
+}
+
+/// Output every report as a single JSON document.
+pub fn display_json(
+    out: &mut impl Write,
+    a: &Assignments,
+    rename_lazy: bool,
+    eliminated: usize,
+    cutoff: Option<&Cutoff>,
+) -> eyre::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, &Report::new(a, rename_lazy, eliminated, cutoff))?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Display why every student did not get a better ranked project.
+pub fn display_explanations(
+    out: &mut impl Write,
+    explanations: &[Explanation],
+) -> std::io::Result<()> {
+    for e in explanations {
+        write!(out, "{student}: ", student = e.student)?;
+        match (&e.project, e.rank) {
+            (Some(project), Some(rank)) => writeln!(out, "{project} (rank {rank})")?,
+            (Some(project), None) => writeln!(out, "{project} (not ranked)")?,
+            (None, _) => writeln!(out, "unassigned")?,
+        }
+        if e.rankings.is_empty() {
+            writeln!(out, "  No ranking")?;
+        } else {
+            // Projects ranked after the one the student got are not relevant
+            let shown = e.rank.unwrap_or(e.rankings.len());
+            let mut rankings = e
+                .rankings
+                .iter()
+                .take(shown)
+                .enumerate()
+                .map(|(rank, tier)| format!("{r}. {tier}", r = rank + 1, tier = tier.join(" = ")))
+                .collect::<Vec<_>>();
+            if shown < e.rankings.len() {
+                rankings.push(String::from("…"));
+            }
+            writeln!(out, "  Ranking: {rankings}", rankings = rankings.join(", "))?;
+        }
+        for u in &e.better {
+            writeln!(
+                out,
+                "  - {project} (rank {rank}): {reason}",
+                project = u.project,
+                rank = u.rank,
+                reason = u.reason
+            )?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub fn display_explanations_json(
+    out: &mut impl Write,
+    explanations: &[Explanation],
+) -> eyre::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, explanations)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Format of the assignments written as CSV.
+#[derive(Default, Deserialize)]
+pub struct CsvConfig {
+    pub columns: Option<Vec<Column>>,
+    delimiter: Option<char>,
+    /// `utf-8`, `utf-8-bom`, or the label of another encoding such as
+    /// `windows-1252`.
+    encoding: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum Column {
+    LastName,
+    FirstName,
+    Name,
+    Login,
+    Email,
+    /// Original id of the student.
+    StudentId,
+    Project,
+    /// Original id of the project.
+    ProjectId,
+    /// Occurrence of the project, starting at 1.
+    Occurrence,
+    Rank,
+    Pinned,
+    Unregistered,
+    /// Bonus of the student for the project.
+    Bonus,
+    Supervisor,
+    SupervisorEmail,
+    Url,
+    Description,
+}
+
+const DEFAULT_COLUMNS: [Column; 3] = [Column::LastName, Column::FirstName, Column::Project];
+
+enum Encoding {
+    Utf8 { bom: bool },
+    Other(&'static encoding_rs::Encoding),
+}
+
+impl CsvConfig {
+    /// Check that the delimiter and the encoding are valid.
+    pub fn check(&self) -> eyre::Result<()> {
+        self.delimiter()?;
+        self.encoding()?;
+        Ok(())
+    }
+
+    fn columns(&self) -> &[Column] {
+        self.columns.as_deref().unwrap_or(&DEFAULT_COLUMNS)
+    }
+
+    fn delimiter(&self) -> eyre::Result<u8> {
+        match self.delimiter {
+            None => Ok(b','),
+            // The quote and line ends would make records ambiguous
+            Some(c @ ('"' | '\n' | '\r')) => bail!("CSV delimiter {c:?} cannot be used"),
+            Some(c) => u8::try_from(c)
+                .ok()
+                .filter(u8::is_ascii)
+                .ok_or_eyre(format!("CSV delimiter `{c}` is not an ASCII character")),
+        }
+    }
+
+    fn encoding(&self) -> eyre::Result<Encoding> {
+        let Some(label) = &self.encoding else {
+            return Ok(Encoding::Utf8 { bom: false });
+        };
+        if label.eq_ignore_ascii_case("utf-8-bom") {
+            return Ok(Encoding::Utf8 { bom: true });
+        }
+        match encoding_rs::Encoding::for_label(label.as_bytes()) {
+            Some(encoding) if encoding == encoding_rs::UTF_8 => Ok(Encoding::Utf8 { bom: false }),
+            // Encodings such as UTF-16 cannot be produced by encoding_rs
+            Some(encoding) if encoding.output_encoding() == encoding => {
+                Ok(Encoding::Other(encoding))
+            }
+            _ => bail!("unsupported CSV encoding `{label}`"),
         }
     }
 }
 
-pub fn display_csv(a: &Assignments) -> eyre::Result<()> {
-    let mut wtr = csv::Writer::from_writer(std::io::stdout());
-    wtr.write_record(["last_name", "first_name", "project"])?;
+impl Column {
+    fn value(self, a: &Assignments, s: StudentId, p: ProjectId, occurrence: usize) -> String {
+        let student = a.student(s);
+        let info = &a.project(p).info;
+        let yes_no = |b| String::from(if b { "yes" } else { "no" });
+        match self {
+            Column::LastName => student.last_name.clone(),
+            Column::FirstName => student.first_name.clone(),
+            Column::Name => student.name.clone(),
+            Column::Login => student.login.clone().unwrap_or_default(),
+            Column::Email => student.email.clone().unwrap_or_default(),
+            Column::StudentId => a.original_student_id(s).0.to_string(),
+            Column::Project => a.project(p).name.clone(),
+            Column::ProjectId => a.original_project_id(p).0.to_string(),
+            Column::Occurrence => occurrence.to_string(),
+            Column::Rank => a
+                .rank_of(s, p)
+                .map(|r| (r + 1).to_string())
+                .unwrap_or_default(),
+            Column::Pinned => yes_no(a.is_pinned_and_has_chosen(s, p)),
+            Column::Unregistered => yes_no(a.is_lazy(s)),
+            Column::Bonus => a.bonus(s, p).map(|b| b.to_string()).unwrap_or_default(),
+            Column::Supervisor => info.supervisor.clone().unwrap_or_default(),
+            Column::SupervisorEmail => info.supervisor_email.clone().unwrap_or_default(),
+            Column::Url => info.url.clone().unwrap_or_default(),
+            Column::Description => info.description.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Output the assigned students, sorted by project then by name, with the
+/// configured columns, delimiter and encoding.
+pub fn display_csv(mut out: impl Write, a: &Assignments, config: &CsvConfig) -> eyre::Result<()> {
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(config.delimiter()?)
+        .from_writer(Vec::new());
+    let columns = config.columns();
+    wtr.write_record(
+        columns
+            .iter()
+            .map(|c| c.to_possible_value().unwrap().get_name().to_owned()),
+    )?;
     let mut projects = a.filter_projects(|p| a.is_open(p));
     projects.sort_by_key(|&p| a.project(p).name.clone());
     for p in projects {
-        let mut students = a.students_for(p).clone();
-        students.sort_by_key(|&s| {
-            (
-                a.student(s).last_name.clone(),
-                a.student(s).first_name.clone(),
-            )
-        });
-        for s in students {
-            wtr.write_record([
-                &a.student(s).last_name,
-                &a.student(s).first_name,
-                &a.project(p).name,
-            ])?;
+        // Occurrences hold students sorted by name
+        for (occurrence, students) in report::occurrences(a, p).into_iter().enumerate() {
+            for s in students {
+                wtr.write_record(columns.iter().map(|c| c.value(a, s, p, occurrence + 1)))?;
+            }
+        }
+    }
+    let text = String::from_utf8(wtr.into_inner()?)?;
+    match config.encoding()? {
+        Encoding::Utf8 { bom } => {
+            if bom {
+                out.write_all("\u{feff}".as_bytes())?;
+            }
+            out.write_all(text.as_bytes())?;
+        }
+        Encoding::Other(encoding) => {
+            let (bytes, _, had_errors) = encoding.encode(&text);
+            if had_errors {
+                let c = text
+                    .chars()
+                    .find(|c| encoding.encode(c.encode_utf8(&mut [0; 4])).2)
+                    .unwrap_or_default();
+                bail!(
+                    "character `{c}` cannot be represented in {name}",
+                    name = encoding.name()
+                );
+            }
+            out.write_all(&bytes)?;
         }
     }
     Ok(())
 }
+
+#[test]
+fn test_display_csv() {
+    use crate::testing;
+    let projects = vec![Project {
+        max_occurrences: 2,
+        ..testing::project(0, "Vidéo", 1, 1)
+    }];
+    let students = ["Zoé", "Łukasz"]
+        .into_iter()
+        .enumerate()
+        .map(|(n, first_name)| Student {
+            bonuses: HashMap::from([(ProjectId(0), 5)]),
+            ..testing::student(n, first_name, &format!("Name {n}"), &[0])
+        })
+        .collect();
+    let mut a = Assignments::new(students, projects);
+    a.set_original_ids(vec![StudentId(10), StudentId(11)], vec![ProjectId(20)]);
+    a.assign_to(StudentId(0), ProjectId(0));
+    a.assign_to(StudentId(1), ProjectId(0));
+    let csv = |config: &str| {
+        let config: CsvConfig = toml::from_str(config).unwrap();
+        let mut out = Vec::new();
+        display_csv(&mut out, &a, &config).map(|()| out)
+    };
+    assert_eq!(
+        String::from_utf8(csv("").unwrap()).unwrap().lines().next(),
+        Some("last_name,first_name,project")
+    );
+    assert_eq!(
+        csv(
+            r#"columns = ["first_name", "student_id", "project_id", "occurrence", "rank", "bonus"]
+               delimiter = ";"
+               encoding = "utf-8-bom""#
+        )
+        .unwrap(),
+        "\u{feff}first_name;student_id;project_id;occurrence;rank;bonus\n\
+         Zoé;10;20;1;1;5\n\
+         Łukasz;11;20;2;1;5\n"
+            .as_bytes()
+    );
+    assert_eq!(
+        csv(r#"columns = ["project"]
+               encoding = "windows-1252""#)
+        .unwrap(),
+        b"project\nVid\xe9o\nVid\xe9o\n"
+    );
+    assert!(
+        csv(r#"columns = ["first_name"]
+               encoding = "windows-1252""#)
+        .is_err()
+    );
+    let config: CsvConfig = toml::from_str(r#"encoding = "utf-16""#).unwrap();
+    assert!(config.check().is_err());
+    let config: CsvConfig = toml::from_str(r#"delimiter = "→""#).unwrap();
+    assert!(config.check().is_err());
+    for delimiter in [r#"'"'"#, r#""\n""#, r#""\r""#] {
+        let config: CsvConfig = toml::from_str(&format!("delimiter = {delimiter}")).unwrap();
+        assert!(config.check().is_err());
+    }
+}
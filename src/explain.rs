@@ -0,0 +1,287 @@
+//! Explanation of the result of every student, telling why each project
+//! they ranked better than the one they got was not available to them.
+
+use crate::hungarian;
+use crate::model::{Assignments, ProjectId, StudentId};
+use crate::report::StudentName;
+use serde::Serialize;
+use std::fmt::Display;
+
+/// The result of a student, with the reason why better ranked projects
+/// were not available.
+#[derive(Serialize)]
+pub struct Explanation {
+    #[serde(flatten)]
+    pub student: StudentName,
+    pub project: Option<String>,
+    /// Rank of the project for the student, starting at 1.
+    pub rank: Option<usize>,
+    /// Project names by tier, best first.
+    pub rankings: Vec<Vec<String>>,
+    /// Projects ranked better than the one the student got, or every ranked
+    /// project if the student did not get any of them.
+    pub better: Vec<Unavailable>,
+}
+
+#[derive(Serialize)]
+pub struct Unavailable {
+    pub project: String,
+    /// Rank of the project for the student, starting at 1.
+    pub rank: usize,
+    #[serde(flatten)]
+    pub reason: Reason,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Reason {
+    /// Every occurrence of the project has been cancelled as not enough
+    /// students could be gathered.
+    Cancelled,
+    /// Nobody got the project, which would need at least `min_students`.
+    NotOpened { min_students: u32 },
+    /// Every seat is taken. Students of the project are split into those
+    /// pinned to it, those with a larger bonus for it, those whose weight
+    /// for it is not worse than the student's one, and the others who have
+    /// been put there for the sake of the overall assignment.
+    Full {
+        seats: u32,
+        cancelled_occurrences: u32,
+        pinned: usize,
+        larger_bonus: usize,
+        ranked_better: usize,
+        others: usize,
+    },
+    /// Seats are left, but the overall assignment is better with the student
+    /// in another project.
+    NotChosen { free_seats: u32 },
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::Cancelled => write!(f, "cancelled as not enough students could be gathered"),
+            Reason::NotOpened { min_students } => {
+                write!(
+                    f,
+                    "not opened, as it needs at least {min_students} students"
+                )
+            }
+            Reason::NotChosen { free_seats } => write!(
+                f,
+                "{free_seats} seat{s} left, but the overall assignment is better \
+                 with the student in another project",
+                s = if *free_seats == 1 { "" } else { "s" }
+            ),
+            Reason::Full {
+                seats,
+                cancelled_occurrences,
+                pinned,
+                larger_bonus,
+                ranked_better,
+                others,
+            } => {
+                write!(f, "full with {seats} seats")?;
+                if *cancelled_occurrences > 0 {
+                    write!(
+                        f,
+                        " after {cancelled_occurrences} cancelled occurrence{s}",
+                        s = if *cancelled_occurrences == 1 { "" } else { "s" }
+                    )?;
+                }
+                let parts = [
+                    (*pinned, "pinned student", "pinned students"),
+                    (
+                        *larger_bonus,
+                        "student with a larger bonus",
+                        "students with a larger bonus",
+                    ),
+                    (
+                        *ranked_better,
+                        "student who ranked it at least as well",
+                        "students who ranked it at least as well",
+                    ),
+                    (
+                        *others,
+                        "student placed there for the sake of the overall assignment",
+                        "students placed there for the sake of the overall assignment",
+                    ),
+                ]
+                .into_iter()
+                .filter(|&(n, _, _)| n > 0)
+                .map(|(n, one, many)| format!("{n} {}", if n == 1 { one } else { many }))
+                .collect::<Vec<_>>();
+                if let Some((last, first)) = parts.split_last() {
+                    write!(f, ", taken by ")?;
+                    if !first.is_empty() {
+                        write!(f, "{} and ", first.join(", "))?;
+                    }
+                    write!(f, "{last}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Explain the result of every student, sorted by name.
+pub fn explanations(a: &Assignments, config: &hungarian::Config) -> Vec<Explanation> {
+    let mut students = a.all_students();
+    students.sort_by_cached_key(|&s| {
+        let student = a.student(s);
+        (student.last_name.clone(), student.first_name.clone())
+    });
+    students
+        .into_iter()
+        .map(|s| explanation(a, config, s))
+        .collect()
+}
+
+pub fn explanation(a: &Assignments, config: &hungarian::Config, s: StudentId) -> Explanation {
+    let project = a.project_for(s);
+    let rank = project.and_then(|p| a.rank_of(s, p));
+    let rankings = a.rankings(s);
+    let better = rankings
+        .iter()
+        .take(rank.unwrap_or(rankings.len()))
+        .enumerate()
+        .flat_map(|(r, tier)| {
+            tier.iter().map(move |&p| Unavailable {
+                project: a.project(p).name.clone(),
+                rank: r + 1,
+                reason: reason(a, config, s, p),
+            })
+        })
+        .collect();
+    Explanation {
+        student: StudentName::from(a.student(s)),
+        project: project.map(|p| a.project(p).name.clone()),
+        rank: rank.map(|r| r + 1),
+        rankings: rankings
+            .iter()
+            .map(|tier| tier.iter().map(|&p| a.project(p).name.clone()).collect())
+            .collect(),
+        better,
+    }
+}
+
+/// Reason why `p` is not the project of `s`.
+fn reason(a: &Assignments, config: &hungarian::Config, s: StudentId, p: ProjectId) -> Reason {
+    if a.is_cancelled(p) {
+        return Reason::Cancelled;
+    }
+    if !a.is_open(p) {
+        return Reason::NotOpened {
+            min_students: a.min_students(p),
+        };
+    }
+    if !a.is_at_capacity(p) {
+        return Reason::NotChosen {
+            free_seats: a.max_capacity(p) - a.size(p),
+        };
+    }
+    let bonus = a.bonus(s, p).unwrap_or(0);
+    let weight = |s| {
+        a.rank_of(s, p)
+            .map(|r| config.rank_weight(r) - a.bonus(s, p).unwrap_or(0))
+    };
+    let (mut pinned, mut larger_bonus, mut ranked_better, mut others) = (0, 0, 0, 0);
+    for &m in a.students_for(p) {
+        if a.is_pinned_and_has_chosen(m, p) {
+            pinned += 1;
+        } else if a.bonus(m, p).unwrap_or(0) > bonus {
+            larger_bonus += 1;
+        } else if weight(m).is_some_and(|w| weight(s).is_none_or(|ws| w <= ws)) {
+            ranked_better += 1;
+        } else {
+            others += 1;
+        }
+    }
+    Reason::Full {
+        seats: a.max_capacity(p),
+        cancelled_occurrences: a.project(p).max_occurrences - a.max_occurrences(p),
+        pinned,
+        larger_bonus,
+        ranked_better,
+        others,
+    }
+}
+
+#[test]
+fn test_explanations() {
+    use crate::model::{Project, Student};
+    use crate::testing;
+    let projects = (0..5)
+        .map(|n| Project {
+            max_occurrences: 2,
+            ..testing::project(n, &format!("Project {n}"), 2, 2)
+        })
+        .collect();
+    let student = |n, rankings: &[usize], bonuses: &[(usize, i64)]| Student {
+        bonuses: bonuses.iter().map(|&(p, b)| (ProjectId(p), b)).collect(),
+        ..testing::student(n, &format!("Student {n}"), &format!("Name {n}"), rankings)
+    };
+    let students = vec![
+        student(0, &[0, 1, 4, 2, 3], &[]),
+        student(1, &[0], &[(0, 100)]),
+        student(2, &[0], &[]),
+        student(3, &[2, 3], &[]),
+        student(4, &[3], &[]),
+        student(5, &[2, 0], &[]),
+    ];
+    let mut a = Assignments::new(students, projects);
+    a.cancel(ProjectId(1));
+    a.cancel_occurrence(ProjectId(0));
+    for (s, p) in [(0, 2), (1, 0), (2, 0), (3, 3), (4, 3), (5, 2)] {
+        a.assign_to(StudentId(s), ProjectId(p));
+    }
+    let reasons = |s| {
+        let explanation = explanation(&a, &hungarian::Config::default(), StudentId(s));
+        explanation
+            .better
+            .into_iter()
+            .map(|u| (u.project, u.rank, u.reason))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        reasons(0),
+        vec![
+            (
+                String::from("Project 0"),
+                1,
+                Reason::Full {
+                    seats: 2,
+                    cancelled_occurrences: 1,
+                    pinned: 0,
+                    larger_bonus: 1,
+                    ranked_better: 1,
+                    others: 0,
+                }
+            ),
+            (String::from("Project 1"), 2, Reason::Cancelled),
+            (
+                String::from("Project 4"),
+                3,
+                Reason::NotOpened { min_students: 2 }
+            ),
+        ]
+    );
+    assert_eq!(
+        reasons(3),
+        vec![(
+            String::from("Project 2"),
+            1,
+            Reason::NotChosen { free_seats: 2 }
+        )]
+    );
+    assert!(reasons(5).is_empty());
+    assert_eq!(
+        reasons(0)[0].2.to_string(),
+        "full with 2 seats after 1 cancelled occurrence, taken by 1 student \
+         with a larger bonus and 1 student who ranked it at least as well"
+    );
+    let explanations = explanations(&a, &hungarian::Config::default());
+    assert_eq!(explanations[0].student.name, "Student 0 Name 0");
+    assert_eq!(explanations[0].rank, Some(4));
+    assert_eq!(explanations[0].rankings[4], vec![String::from("Project 3")]);
+}
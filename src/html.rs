@@ -0,0 +1,374 @@
+//! Standalone HTML version of the report, which can be opened in a browser
+//! without any other file.
+
+use crate::loaders::Cutoff;
+use crate::model::Assignments;
+use crate::report::{self, Stats};
+use std::io::Write;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin: 0.5em 0 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }
+th { background: #eee; cursor: pointer; user-select: none; }
+th::after { content: ' \\2195'; color: #999; }
+td.number { text-align: right; }
+.info { color: #555; margin: 0.2em 0; }
+svg text { font-size: 12px; }
+svg rect { fill: #4a7ab5; }
+";
+
+/// Sort a table when clicking on a column header, numerically if every
+/// non-empty cell of the column is marked as a number.
+const SCRIPT: &str = "
+document.querySelectorAll('table.sortable th').forEach((th, column) => {
+  th.addEventListener('click', () => {
+    const tbody = th.closest('table').querySelector('tbody');
+    const rows = Array.from(tbody.rows);
+    const cell = row => row.cells[column].textContent.trim();
+    const numeric = rows.every(row => cell(row) === '' || row.cells[column].classList.contains('number'));
+    const ascending = th.dataset.order !== 'ascending';
+    th.closest('tr').querySelectorAll('th').forEach(h => delete h.dataset.order);
+    th.dataset.order = ascending ? 'ascending' : 'descending';
+    rows.sort((r1, r2) => {
+      const [c1, c2] = [cell(r1), cell(r2)];
+      const order = numeric
+        ? (c1 === '' ? Infinity : Number(c1)) - (c2 === '' ? Infinity : Number(c2))
+        : c1.localeCompare(c2);
+      return ascending ? order : -order;
+    });
+    rows.forEach(row => tbody.appendChild(row));
+  });
+});
+";
+
+/// Write the full report as a standalone HTML document.
+pub fn write_html(
+    out: &mut impl Write,
+    a: &Assignments,
+    rename_lazy: bool,
+    eliminated: usize,
+    cutoff: Option<&Cutoff>,
+) -> std::io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Project assignments</title>")?;
+    writeln!(out, "<style>{STYLE}</style>\n</head>\n<body>")?;
+    writeln!(out, "<h1>Project assignments</h1>")?;
+    write_stats(out, &report::stats(a, eliminated, cutoff))?;
+    write_projects(out, a, rename_lazy)?;
+    write_remarks(out, a)?;
+    writeln!(out, "<script>{SCRIPT}</script>\n</body>\n</html>")
+}
+
+/// Write the open projects with their rosters.
+fn write_projects(out: &mut impl Write, a: &Assignments, rename_lazy: bool) -> std::io::Result<()> {
+    writeln!(out, "<h2>Projects</h2>")?;
+    for project in report::details(a, rename_lazy) {
+        write!(out, "<h3>{name}", name = escape(&project.name))?;
+        if project.occurrences > 1 {
+            write!(out, " ({occ} occurrences)", occ = project.occurrences)?;
+        }
+        writeln!(out, "</h3>")?;
+        let info = &project.info;
+        if let Some(contact) = info.supervisor_contact() {
+            writeln!(
+                out,
+                "<p class=\"info\">Supervisor: {contact}</p>",
+                contact = escape(&contact)
+            )?;
+        }
+        if let Some(url) = &info.url {
+            writeln!(out, "<p class=\"info\">URL: {link}</p>", link = link(url))?;
+        }
+        if let Some(description) = &info.description {
+            writeln!(
+                out,
+                "<p class=\"info\">{description}</p>",
+                description = escape(description)
+            )?;
+        }
+        table_start(out, &["Student", "Login", "Rank", "Pinned"])?;
+        for s in &project.students {
+            row(
+                out,
+                &[
+                    Cell::Text(escape(&s.student.name)),
+                    Cell::Text(escape(s.student.login.as_deref().unwrap_or_default())),
+                    Cell::Number(s.rank.map(|r| r.to_string()).unwrap_or_default()),
+                    Cell::Text(String::from(if s.pinned { "yes" } else { "" })),
+                ],
+            )?;
+        }
+        table_end(out)?;
+    }
+    Ok(())
+}
+
+/// Write the missed bonuses, the empty projects and the projects with many
+/// unregistered students.
+fn write_remarks(out: &mut impl Write, a: &Assignments) -> std::io::Result<()> {
+    let missed_bonuses = report::missed_bonuses(a);
+    if !missed_bonuses.is_empty() {
+        writeln!(out, "<h2>Useless bonuses</h2>")?;
+        table_start(
+            out,
+            &[
+                "Student",
+                "Project",
+                "Rank",
+                "Bonus project",
+                "Bonus rank",
+                "Bonus",
+            ],
+        )?;
+        for m in missed_bonuses {
+            row(
+                out,
+                &[
+                    Cell::Text(escape(&m.student.to_string())),
+                    Cell::Text(escape(&m.project)),
+                    Cell::Number(m.rank.to_string()),
+                    Cell::Text(escape(&m.bonus_project)),
+                    Cell::Number(m.bonus_rank.to_string()),
+                    Cell::Number(m.bonus.to_string()),
+                ],
+            )?;
+        }
+        table_end(out)?;
+    }
+    let empty = report::empty(a);
+    if !empty.is_empty() {
+        writeln!(out, "<h2>Empty projects</h2>\n<ul>")?;
+        for project in empty {
+            writeln!(out, "<li>{project}</li>", project = escape(&project))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+    let many_lazy = report::with_many_lazy(a);
+    if !many_lazy.is_empty() {
+        writeln!(
+            out,
+            "<h2>Projects with at least half the members being unregistered students</h2>"
+        )?;
+        table_start(out, &["Project", "Unregistered", "Total"])?;
+        for p in many_lazy {
+            row(
+                out,
+                &[
+                    Cell::Text(escape(&p.project)),
+                    Cell::Number(p.unregistered.to_string()),
+                    Cell::Number(p.total.to_string()),
+                ],
+            )?;
+        }
+        table_end(out)?;
+    }
+    Ok(())
+}
+
+fn write_stats(out: &mut impl Write, stats: &Stats) -> std::io::Result<()> {
+    writeln!(out, "<h2>Summary</h2>\n<ul>")?;
+    writeln!(
+        out,
+        "<li>Registered students: {registered}</li>",
+        registered = stats.registered
+    )?;
+    writeln!(
+        out,
+        "<li>{kind} students: {unregistered}</li>",
+        kind = if stats.dropped_unregistered {
+            "Unconsidered"
+        } else {
+            "Unregistered"
+        },
+        unregistered = stats.unregistered
+    )?;
+    writeln!(out, "<li>Total: {total}</li>", total = stats.total)?;
+    writeln!(
+        out,
+        "<li>Projects/occurrences: {proj}/{occ}</li>",
+        proj = stats.projects,
+        occ = stats.occurrences
+    )?;
+    if let Some(cutoff) = &stats.cutoff {
+        writeln!(
+            out,
+            "<li>Ignored after {as_of}: {p} preferences, {b} bonuses</li>",
+            as_of = cutoff.as_of,
+            p = cutoff.late_preferences,
+            b = cutoff.late_bonuses
+        )?;
+    }
+    writeln!(out, "</ul>")?;
+    writeln!(out, "<h2>Final ranking</h2>")?;
+    write_histogram(out, stats)?;
+    table_start(out, &["Rank", "Students", "Cumulative", "Percent"])?;
+    for rank in &stats.ranks {
+        row(
+            out,
+            &[
+                Cell::Number(rank.rank.to_string()),
+                Cell::Number(rank.students.to_string()),
+                Cell::Number(rank.cumulative.to_string()),
+                Cell::Number(format!("{:.2}", rank.percent)),
+            ],
+        )?;
+    }
+    table_end(out)
+}
+
+/// Draw the number of students at each rank as an SVG bar chart, with a
+/// slot for every rank up to the worst one given.
+fn write_histogram(out: &mut impl Write, stats: &Stats) -> std::io::Result<()> {
+    const BAR: usize = 40;
+    const HEIGHT: usize = 150;
+    let max = stats.ranks.iter().map(|r| r.students).max().unwrap_or(0);
+    if max == 0 {
+        return Ok(());
+    }
+    let mut counts = vec![0; stats.ranks.last().map_or(0, |r| r.rank)];
+    for rank in &stats.ranks {
+        counts[rank.rank - 1] = rank.students;
+    }
+    writeln!(
+        out,
+        "<svg width=\"{w}\" height=\"{h}\" role=\"img\" aria-label=\"Rank histogram\">",
+        w = BAR * counts.len(),
+        h = HEIGHT + 40
+    )?;
+    for (i, &n) in counts.iter().enumerate() {
+        let x = i * BAR;
+        if n > 0 {
+            let height = n * HEIGHT / max;
+            let y = 20 + HEIGHT - height;
+            write!(
+                out,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{height}\"/>\
+                 <text x=\"{c}\" y=\"{t}\" text-anchor=\"middle\">{n}</text>",
+                x = x + 4,
+                w = BAR - 8,
+                c = x + BAR / 2,
+                t = y - 4,
+            )?;
+        }
+        writeln!(
+            out,
+            "<text x=\"{c}\" y=\"{b}\" text-anchor=\"middle\">{r}</text>",
+            c = x + BAR / 2,
+            b = HEIGHT + 36,
+            r = i + 1
+        )?;
+    }
+    writeln!(out, "</svg>")
+}
+
+fn table_start(out: &mut impl Write, headers: &[&str]) -> std::io::Result<()> {
+    write!(out, "<table class=\"sortable\">\n<thead><tr>")?;
+    for header in headers {
+        write!(out, "<th>{header}</th>")?;
+    }
+    writeln!(out, "</tr></thead>\n<tbody>")
+}
+
+/// Content of a table cell, already escaped. Numbers are right-aligned, and
+/// sorted numerically.
+enum Cell {
+    Text(String),
+    Number(String),
+}
+
+fn row(out: &mut impl Write, cells: &[Cell]) -> std::io::Result<()> {
+    write!(out, "<tr>")?;
+    for cell in cells {
+        match cell {
+            Cell::Text(text) => write!(out, "<td>{text}</td>")?,
+            Cell::Number(number) => write!(out, "<td class=\"number\">{number}</td>")?,
+        }
+    }
+    writeln!(out, "</tr>")
+}
+
+fn table_end(out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(out, "</tbody>\n</table>")
+}
+
+/// Link to an URL, which is only made clickable if it uses HTTP.
+fn link(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        format!("<a href=\"{url}\">{url}</a>", url = escape(url))
+    } else {
+        escape(url)
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(
+        escape(r#"<a href="x">Tom & Jerry's</a>"#),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+    );
+    assert_eq!(
+        link("https://example.com/?a=1&b=2"),
+        "<a href=\"https://example.com/?a=1&amp;b=2\">https://example.com/?a=1&amp;b=2</a>"
+    );
+    assert_eq!(link("javascript:alert(1)"), "javascript:alert(1)");
+}
+
+#[test]
+fn test_write_html() {
+    use crate::model::{ProjectId, StudentId};
+    use crate::testing;
+    let projects = ["NaN", "inf", "Other"]
+        .into_iter()
+        .enumerate()
+        .map(|(n, name)| testing::project(n, name, 1, 2))
+        .collect();
+    let rankings: [&[usize]; 4] = [&[0, 1], &[0, 1], &[2, 0, 1], &[]];
+    let students = rankings
+        .into_iter()
+        .enumerate()
+        .map(|(n, ranking)| {
+            testing::student(n, &format!("First {n}"), &format!("Last {n}"), ranking)
+        })
+        .collect();
+    let mut a = Assignments::new(students, projects);
+    for (s, p) in [(0, 0), (1, 0), (2, 1), (3, 1)] {
+        a.assign_to(StudentId(s), ProjectId(p));
+    }
+    let mut out = Vec::new();
+    write_html(&mut out, &a, false, 0, None).unwrap();
+    let html = String::from_utf8(out).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>\n"));
+    assert!(html.ends_with("</html>\n"));
+    assert!(
+        html.contains(
+            "<tr><td>First 0 Last 0</td><td></td><td class=\"number\">1</td><td></td></tr>"
+        )
+    );
+    // Project names are never taken for numbers
+    assert!(
+        html.contains(
+            "<tr><td>inf</td><td class=\"number\">1</td><td class=\"number\">2</td></tr>"
+        )
+    );
+    // Nobody got their second choice, which still gets a slot
+    assert!(html.contains("<svg width=\"120\""));
+    assert_eq!(html.matches("<rect ").count(), 2);
+    assert!(html.contains("<text x=\"60\" y=\"186\" text-anchor=\"middle\">2</text>"));
+}
@@ -3,15 +3,47 @@
 use crate::model::{Assignments, ProjectId, StudentId};
 use eyre::bail;
 use pathfinding::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::hash_map::HashMap;
 use std::time::Instant;
 use tracing::{debug, info, instrument, trace};
 
-#[derive(Deserialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct Config {
     rank_mult: Option<i64>,
     rank_pow: Option<u32>,
+    stability_penalty: Option<i64>,
+}
+
+impl Config {
+    pub fn rank_mult(&self) -> i64 {
+        self.rank_mult.unwrap_or(3)
+    }
+
+    pub fn rank_pow(&self) -> u32 {
+        self.rank_pow.unwrap_or(4)
+    }
+
+    /// Weight of a project ranked at `rank` (starting at 0), before
+    /// bonuses are taken into account.
+    pub fn rank_weight(&self, rank: usize) -> i64 {
+        (rank as i64 * self.rank_mult()).pow(self.rank_pow())
+    }
+
+    /// Weight added for every project other than the one the student was
+    /// previously assigned to. 0 disables the stability mode.
+    pub fn stability_penalty(&self) -> i64 {
+        self.stability_penalty.unwrap_or(0)
+    }
+
+    /// Return the same configuration with default values made explicit.
+    pub fn resolved(&self) -> Config {
+        Config {
+            rank_mult: Some(self.rank_mult()),
+            rank_pow: Some(self.rank_pow()),
+            stability_penalty: Some(self.stability_penalty()),
+        }
+    }
 }
 
 #[instrument(skip_all)]
@@ -20,11 +52,7 @@ pub fn assign(assignments: &mut Assignments, config: &Config) -> eyre::Result<()
     // Check that we have enough open positions for all our students.
     assignments.check_number_of_seats(false)?;
     // Compute the best assignments
-    let weights = compute_weights(
-        assignments,
-        config.rank_mult.unwrap_or(3),
-        config.rank_pow.unwrap_or(4),
-    );
+    let weights = compute_weights(assignments, config);
     Hungarian {
         assignments,
         weights,
@@ -35,7 +63,10 @@ pub fn assign(assignments: &mut Assignments, config: &Config) -> eyre::Result<()
 }
 
 /// Compute the weights indexed by student then by project (less is better).
-fn compute_weights(a: &Assignments, rank_mult: i64, rank_pow: u32) -> Matrix<i64> {
+/// Moving a student away from their previous project costs
+/// `stability_penalty`.
+fn compute_weights(a: &Assignments, config: &Config) -> Matrix<i64> {
+    let stability_penalty = config.stability_penalty();
     let slen = a.all_students().len() as i64;
     let mut seats = Vec::new();
     let mut seats_for = HashMap::new();
@@ -56,9 +87,14 @@ fn compute_weights(a: &Assignments, rank_mult: i64, rank_pow: u32) -> Matrix<i64
                 weights[(s.0, p.0)] = if a.is_pinned_and_has_chosen(s, p) {
                     -large
                 } else {
-                    (rank as i64 * rank_mult).pow(rank_pow) - a.bonus(s, p).unwrap_or(0)
+                    config.rank_weight(rank) - a.bonus(s, p).unwrap_or(0)
                 };
             }
+            if a.previous_project_for(s)
+                .is_some_and(|previous| previous != p)
+            {
+                weights[(s.0, p.0)] += stability_penalty;
+            }
         }
     }
     weights
@@ -84,6 +120,16 @@ impl Hungarian<'_> {
             .sum::<i64>()
     }
 
+    /// Take one of the unassigned students to put it into a project, preferably
+    /// one which was previously assigned to it.
+    fn take_student_for(&self, unassigned: &mut Vec<StudentId>, project: ProjectId) -> StudentId {
+        let i = unassigned
+            .iter()
+            .position(|&s| self.assignments.previous_project_for(s) == Some(project))
+            .unwrap_or(unassigned.len() - 1);
+        unassigned.remove(i)
+    }
+
     /// Assign every student to a project. There must be enough seats for every
     /// student or this function will panic.
     fn hungarian_algorithm(&mut self) {
@@ -143,10 +189,10 @@ impl Hungarian<'_> {
                 return;
             }
             for _ in 0..missing {
-                let s = unassigned.pop().unwrap();
+                let s = self.take_student_for(&mut unassigned, p);
                 trace!(
                     project = %self.assignments.project(p).name,
-                    student = %self.assignments.student(s).name,
+                    student = %self.assignments.student(s),
                     "Assigning to incomplete project",
                 );
                 self.assignments.assign_to(s, p);
@@ -155,7 +201,8 @@ impl Hungarian<'_> {
     }
 
     /// Complete non-full projects with unassigned students. It will
-    /// never make an acceptable project unacceptable.
+    /// never make an acceptable project unacceptable. Students go back to
+    /// their previous project when possible.
     #[instrument(skip_all)]
     fn complete_non_full_projects(&mut self) {
         for s in self.assignments.unassigned_students() {
@@ -171,6 +218,7 @@ impl Hungarian<'_> {
                 .min_by_key(|&p| {
                     assert_eq!(self.assignments.open_spots_for(p)[0], 1);
                     (
+                        self.assignments.previous_project_for(s) != Some(p),
                         self.assignments.lazy_students_count_for(p),
                         -(self.assignments.open_spots_for(p).last().copied().unwrap() as isize),
                         self.total_weight_for(p),
@@ -179,7 +227,7 @@ impl Hungarian<'_> {
             {
                 trace!(
                     project = %self.assignments.project(p).name,
-                    student = %self.assignments.student(s).name,
+                    student = %self.assignments.student(s),
                     lazy_students = %self.assignments.lazy_students_count_for(p),
                     max_open_spots = %self.assignments.open_spots_for(p).last().unwrap(),
                     "Assigning student to non-full project",
@@ -225,7 +273,8 @@ impl Hungarian<'_> {
                     .len()
                     .min(self.assignments.project(p).min_students as usize)
                 {
-                    self.assignments.assign_to(unassigned.pop().unwrap(), p);
+                    let s = self.take_student_for(&mut unassigned, p);
+                    self.assignments.assign_to(s, p);
                 }
             } else {
                 {
@@ -245,6 +294,8 @@ impl Hungarian<'_> {
 
     /// If it exists, find one of the best unacceptable project occurrence
     /// to cancel. Or even an acceptable one if `including_acceptable` is true.
+    /// Occurrences which were needed by the previous assignments are kept
+    /// as long as possible.
     #[instrument(skip_all)]
     fn find_occurrence_to_cancel(&self, including_acceptable: bool) -> Option<ProjectId> {
         self.assignments
@@ -267,8 +318,11 @@ impl Hungarian<'_> {
                     .copied()
                     .unwrap_or(0);
                 let all_lazy = students.iter().all(|&s| self.assignments.is_lazy(s));
+                let previously_unused =
+                    self.assignments.max_occurrences(p) > self.assignments.previous_occurrences(p);
                 (
                     all_lazy,
+                    previously_unused,
                     self.assignments.max_occurrences(p),
                     -pinned,
                     missing,
@@ -298,7 +352,8 @@ impl Hungarian<'_> {
         self.complete_incomplete_projects();
 
         // If we have projects which are not satisfied, remove one occurrence
-        // (preferably in projects with many occurrences) and start again.
+        // (preferably one not needed by the previous assignments, and in
+        // projects with many occurrences) and start again.
         // The number of pinned students also decreases the probability of removing
         // the project.
         if let Some(to_cancel) = self.find_occurrence_to_cancel(false) {
@@ -348,3 +403,29 @@ impl Hungarian<'_> {
         Ok(())
     }
 }
+
+#[test]
+fn test_tied_projects() {
+    use crate::model::Student;
+    use crate::testing;
+    let projects = (0..3)
+        .map(|n| testing::project(n, &format!("Project {n}"), 1, 1))
+        .collect();
+    let students = (0..2)
+        .map(|n| Student {
+            rankings: vec![vec![ProjectId(0), ProjectId(1)], vec![ProjectId(2)]],
+            ..testing::student(n, &format!("Student {n}"), &format!("Name {n}"), &[])
+        })
+        .collect();
+    let mut a = Assignments::new(students, projects);
+    let weights = compute_weights(&a, &Config::default());
+    for s in 0..2 {
+        assert_eq!(weights[(s, 0)], weights[(s, 1)]);
+        assert!(weights[(s, 1)] < weights[(s, 2)]);
+    }
+    assign(&mut a, &Config::default()).unwrap();
+    let stats = crate::report::stats(&a, 0, None);
+    assert_eq!(stats.ranks.len(), 1);
+    assert_eq!(stats.ranks[0].rank, 1);
+    assert_eq!(stats.ranks[0].students, 2);
+}
@@ -0,0 +1,289 @@
+use super::{Attacher, Backend, Cutoff, Filter, WeightRow};
+use crate::model::{Project, ProjectId, ProjectInfo, Student, StudentId};
+use crate::timestamp::Timestamp;
+use crate::validation::Problem;
+use eyre::Context;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A backend reading CSV files from a directory:
+///
+/// - `projects.csv`: `id`, `intitule`, `quota_min`, `quota_max`, `occurrences`,
+///   and optionally `url`, `encadrant`, `encadrant_email` and `description`
+/// - `students.csv`: `id`, `prenom`, `nom`, and optionally `login` and `email`
+/// - `preferences.csv`: `eleve_id`, `projet_id`, `poids`, and optionally `date`
+/// - `bonuses.csv` (optional): `eleve_id`, `projet_id`, `poids`, and optionally `date`
+///
+/// Assignments are written into `assignments.csv` as `eleve_id`, `projet_id`
+/// records, with an empty `projet_id` for unassigned students.
+pub struct CsvDirectory {
+    dir: PathBuf,
+    filter: Filter,
+    problems: Vec<Problem>,
+    cutoff: Option<Cutoff>,
+}
+
+#[derive(Deserialize)]
+struct ProjectRecord {
+    id: usize,
+    intitule: String,
+    quota_min: u32,
+    quota_max: u32,
+    occurrences: u32,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    encadrant: Option<String>,
+    #[serde(default)]
+    encadrant_email: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StudentRecord {
+    id: usize,
+    prenom: String,
+    nom: String,
+    #[serde(default)]
+    login: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PreferenceRecord {
+    eleve_id: usize,
+    projet_id: usize,
+    poids: i64,
+    #[serde(default)]
+    date: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct AssignmentRecord {
+    eleve_id: usize,
+    projet_id: Option<usize>,
+}
+
+impl CsvDirectory {
+    pub fn new<P: AsRef<Path>>(dir: P, filter: Filter) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            filter,
+            problems: Vec::new(),
+            cutoff: None,
+        }
+    }
+
+    fn read<T: DeserializeOwned>(&self, file_name: &str) -> eyre::Result<Vec<T>> {
+        let path = self.dir.join(file_name);
+        csv::Reader::from_path(&path)
+            .context(format!("cannot open `{}`", path.display()))?
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .context(format!("invalid record in `{}`", path.display()))
+    }
+
+    /// Read preferences or bonuses, ordered by student and weight. Rows dated
+    /// after the cutoff, if any, are ignored, and their number is returned
+    /// alongside the other rows.
+    fn read_preferences(&self, file_name: &str) -> eyre::Result<(Vec<WeightRow>, usize)> {
+        let mut rows = Vec::new();
+        let mut late = 0;
+        for r in self.read::<PreferenceRecord>(file_name)? {
+            if let (Some(as_of), Some(date)) = (&self.filter.as_of, &r.date) {
+                let date = date
+                    .parse::<Timestamp>()
+                    .context(format!("invalid date in `{file_name}`"))?;
+                if date > *as_of {
+                    late += 1;
+                    continue;
+                }
+            }
+            rows.push((StudentId(r.eleve_id), ProjectId(r.projet_id), r.poids));
+        }
+        rows.sort_by_key(|&(s, _, w)| (s, w));
+        Ok((rows, late))
+    }
+}
+
+impl Backend for CsvDirectory {
+    async fn load(&mut self) -> eyre::Result<(Vec<Student>, Vec<Project>)> {
+        let projects = self
+            .read::<ProjectRecord>("projects.csv")?
+            .into_iter()
+            .map(|r| Project {
+                id: ProjectId(r.id),
+                name: r.intitule,
+                min_students: r.quota_min,
+                max_students: r.quota_max,
+                max_occurrences: r.occurrences,
+                info: ProjectInfo {
+                    url: r.url,
+                    supervisor: r.encadrant,
+                    supervisor_email: r.encadrant_email,
+                    description: r.description,
+                },
+            })
+            .collect::<Vec<_>>();
+        let mut students = self
+            .read::<StudentRecord>("students.csv")?
+            .into_iter()
+            .map(|r| Student {
+                login: r.login,
+                email: r.email,
+                ..Student::new(StudentId(r.id), r.prenom, r.nom, Vec::new(), HashMap::new())
+            })
+            .collect::<Vec<_>>();
+        let (preferences, late_preferences) = self.read_preferences("preferences.csv")?;
+        let (bonuses, late_bonuses) = if self.dir.join("bonuses.csv").exists() {
+            self.read_preferences("bonuses.csv")?
+        } else {
+            (Vec::new(), 0)
+        };
+        let mut attacher = Attacher::new(&mut students);
+        for row in preferences {
+            attacher.preference(row);
+        }
+        for row in bonuses {
+            attacher.bonus(row);
+        }
+        self.problems = attacher.finish(&projects);
+        self.cutoff = Cutoff::new(&self.filter, late_preferences, late_bonuses);
+        self.problems
+            .extend(self.cutoff.iter().flat_map(Cutoff::problems));
+        Ok((students, projects))
+    }
+
+    async fn load_assignments(&mut self) -> eyre::Result<Vec<(StudentId, ProjectId)>> {
+        if !self.dir.join("assignments.csv").exists() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .read::<AssignmentRecord>("assignments.csv")?
+            .into_iter()
+            .filter_map(|r| r.projet_id.map(|p| (StudentId(r.eleve_id), ProjectId(p))))
+            .collect())
+    }
+
+    async fn save_assignments(
+        &mut self,
+        assignments: &[(StudentId, ProjectId)],
+        unassigned: &[StudentId],
+    ) -> eyre::Result<()> {
+        let path = self.dir.join("assignments.csv");
+        let mut wtr =
+            csv::Writer::from_path(&path).context(format!("cannot create `{}`", path.display()))?;
+        for &(s, p) in assignments {
+            wtr.serialize(AssignmentRecord {
+                eleve_id: s.0,
+                projet_id: Some(p.0),
+            })?;
+        }
+        for &s in unassigned {
+            wtr.serialize(AssignmentRecord {
+                eleve_id: s.0,
+                projet_id: None,
+            })?;
+        }
+        wtr.flush()
+            .context(format!("cannot write `{}`", path.display()))?;
+        Ok(())
+    }
+
+    fn load_problems(&self) -> Vec<Problem> {
+        self.problems.clone()
+    }
+
+    fn cutoff(&self) -> Option<Cutoff> {
+        self.cutoff.clone()
+    }
+}
+
+#[tokio::test]
+async fn test_csv_directory() {
+    let dir = std::env::temp_dir().join(format!("rsolver-csv-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("projects.csv"),
+        "id,intitule,quota_min,quota_max,occurrences,encadrant\n3,Robots,2,4,1,Grace Hopper\n7,Compilers,1,3,2,\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("students.csv"),
+        "id,prenom,nom,login\n12,Ada,Lovelace,alovelace\n15,Alan,Turing,\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("preferences.csv"),
+        "eleve_id,projet_id,poids\n12,7,2\n12,3,1\n15,7,1\n15,3,1\n99,7,1\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("bonuses.csv"),
+        "eleve_id,projet_id,poids,date\n15,3,-1000,2018-06-12 10:00:00\n",
+    )
+    .unwrap();
+    let mut backend = CsvDirectory::new(&dir, Filter::default());
+    let (students, projects) = backend.load().await.unwrap();
+    assert_eq!(projects.len(), 2);
+    assert_eq!(projects[1].max_occurrences, 2);
+    assert_eq!(projects[0].info.supervisor.as_deref(), Some("Grace Hopper"));
+    assert_eq!(projects[1].info.supervisor, None);
+    assert_eq!(students[0].name, "Ada Lovelace");
+    assert_eq!(students[0].login.as_deref(), Some("alovelace"));
+    assert_eq!(students[1].login, None);
+    assert_eq!(
+        students[0].rankings,
+        vec![vec![ProjectId(3)], vec![ProjectId(7)]]
+    );
+    assert_eq!(students[1].rankings, vec![vec![ProjectId(7), ProjectId(3)]]);
+    assert_eq!(students[1].bonuses.get(&ProjectId(3)), Some(&1000));
+    assert_eq!(
+        backend.load_problems(),
+        vec![Problem::UnknownPreferenceStudent {
+            student: StudentId(99),
+            project: ProjectId(7)
+        }]
+    );
+    let mut late = CsvDirectory::new(
+        &dir,
+        Filter {
+            as_of: Some("2018-06-11".parse().unwrap()),
+            ..Filter::default()
+        },
+    );
+    let (students, _) = late.load().await.unwrap();
+    assert!(students[1].bonuses.is_empty());
+    assert_eq!(
+        late.load_problems()[1],
+        Problem::LateBonuses {
+            count: 1,
+            as_of: "2018-06-11 23:59:59".parse().unwrap()
+        }
+    );
+    assert_eq!(
+        late.cutoff(),
+        Some(Cutoff {
+            as_of: "2018-06-11 23:59:59".parse().unwrap(),
+            late_preferences: 0,
+            late_bonuses: 1,
+        })
+    );
+    assert_eq!(backend.cutoff(), None);
+    backend
+        .save_assignments(&[(StudentId(12), ProjectId(3))], &[StudentId(15)])
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(dir.join("assignments.csv")).unwrap(),
+        "eleve_id,projet_id\n12,3\n15,\n"
+    );
+    assert_eq!(
+        backend.load_assignments().await.unwrap(),
+        vec![(StudentId(12), ProjectId(3))]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,42 @@
+use super::Backend;
+use crate::model::{Project, ProjectId, Student, StudentId};
+
+/// A backend holding all its data in memory. Saved assignments are
+/// kept so that they can be inspected afterwards.
+#[derive(Default)]
+pub struct MemoryBackend {
+    pub students: Vec<Student>,
+    pub projects: Vec<Project>,
+    pub assignments: Vec<(StudentId, ProjectId)>,
+    pub unassigned: Vec<StudentId>,
+}
+
+impl MemoryBackend {
+    pub fn new(students: Vec<Student>, projects: Vec<Project>) -> Self {
+        Self {
+            students,
+            projects,
+            ..Self::default()
+        }
+    }
+}
+
+impl Backend for MemoryBackend {
+    async fn load(&mut self) -> eyre::Result<(Vec<Student>, Vec<Project>)> {
+        Ok((self.students.clone(), self.projects.clone()))
+    }
+
+    async fn load_assignments(&mut self) -> eyre::Result<Vec<(StudentId, ProjectId)>> {
+        Ok(self.assignments.clone())
+    }
+
+    async fn save_assignments(
+        &mut self,
+        assignments: &[(StudentId, ProjectId)],
+        unassigned: &[StudentId],
+    ) -> eyre::Result<()> {
+        self.assignments = assignments.to_vec();
+        self.unassigned = unassigned.to_vec();
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use crate::hungarian;
+use crate::model::{Project, ProjectId, Student, StudentId};
+use crate::timestamp::Timestamp;
+use crate::validation::Problem;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::trace;
+
+pub use self::directory::CsvDirectory;
+pub use self::memory::MemoryBackend;
+pub use self::schema::Schema;
+pub use self::sql::{HistoryRun, Loader};
+pub use self::workbook::load_workbook;
+
+mod directory;
+mod memory;
+mod schema;
+mod sql;
+mod workbook;
+
+/// A `(student, project, weight)` row of preferences or bonuses.
+type WeightRow = (StudentId, ProjectId, i64);
+
+/// Restrictions on the data loaded by a backend.
+#[derive(Clone, Default)]
+pub struct Filter {
+    /// Ignore preferences and bonuses dated after this timestamp.
+    pub as_of: Option<Timestamp>,
+    /// Only consider projects and students of this campaign, as well as
+    /// their preferences and bonuses. Only the SQL loader honors it.
+    pub campaign: Option<String>,
+}
+
+/// The cutoff applied to preferences and bonuses by a load, with the number
+/// of rows which have been ignored because they were dated after it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Cutoff {
+    pub as_of: Timestamp,
+    pub late_preferences: usize,
+    pub late_bonuses: usize,
+}
+
+impl Cutoff {
+    /// Build the cutoff of `filter`, if any, with the number of late
+    /// preferences and bonuses.
+    fn new(filter: &Filter, late_preferences: usize, late_bonuses: usize) -> Option<Self> {
+        filter.as_of.clone().map(|as_of| Cutoff {
+            as_of,
+            late_preferences,
+            late_bonuses,
+        })
+    }
+
+    /// Report the late preferences and bonuses, if any, as problems.
+    fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        if self.late_preferences > 0 {
+            problems.push(Problem::LatePreferences {
+                count: self.late_preferences,
+                as_of: self.as_of.clone(),
+            });
+        }
+        if self.late_bonuses > 0 {
+            problems.push(Problem::LateBonuses {
+                count: self.late_bonuses,
+                as_of: self.as_of.clone(),
+            });
+        }
+        problems
+    }
+}
+
+/// The result of a committed run, with the parameters used to compute it.
+/// Identifiers are the original ones as returned by [`Backend::load`].
+#[derive(Clone)]
+pub struct Run {
+    pub hungarian: hungarian::Config,
+    pub drop_unregistered: bool,
+    /// Cutoff applied to the preferences and bonuses, if any.
+    pub cutoff: Option<Cutoff>,
+    /// Every student, in increasing id order.
+    pub assignments: Vec<RunAssignment>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunAssignment {
+    pub student: StudentId,
+    pub project: Option<ProjectId>,
+    /// Rank of the project for the student, starting at 0, if the student
+    /// ranked it.
+    pub rank: Option<usize>,
+}
+
+/// Summary statistics of a run.
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RunStats {
+    pub students: usize,
+    pub assigned: usize,
+    /// Assigned students who did not rank their project.
+    pub unregistered: usize,
+    /// Number of students assigned at each rank, best first.
+    pub ranks: Vec<usize>,
+}
+
+impl Run {
+    /// Split the run into assigned and unassigned students.
+    pub fn split(&self) -> (Vec<(StudentId, ProjectId)>, Vec<StudentId>) {
+        let assigned = self
+            .assignments
+            .iter()
+            .filter_map(|a| a.project.map(|p| (a.student, p)))
+            .collect();
+        let unassigned = self
+            .assignments
+            .iter()
+            .filter(|a| a.project.is_none())
+            .map(|a| a.student)
+            .collect();
+        (assigned, unassigned)
+    }
+
+    pub fn stats(&self) -> RunStats {
+        let mut ranks = Vec::new();
+        for rank in self.assignments.iter().filter_map(|a| a.rank) {
+            if ranks.len() <= rank {
+                ranks.resize(rank + 1, 0);
+            }
+            ranks[rank] += 1;
+        }
+        let assigned = self
+            .assignments
+            .iter()
+            .filter(|a| a.project.is_some())
+            .count();
+        RunStats {
+            students: self.assignments.len(),
+            assigned,
+            unregistered: assigned - ranks.iter().sum::<usize>(),
+            ranks,
+        }
+    }
+}
+
+/// A source of students and projects, which is also able to store the
+/// results of the assignment.
+pub trait Backend {
+    /// Load the students, with their rankings and bonuses, and the projects.
+    async fn load(&mut self) -> eyre::Result<(Vec<Student>, Vec<Project>)>;
+
+    /// Load the currently stored assignments.
+    async fn load_assignments(&mut self) -> eyre::Result<Vec<(StudentId, ProjectId)>>;
+
+    /// Store the assignments, and remove any existing assignment for
+    /// unassigned students. Identifiers are the original ones as returned
+    /// by [`Backend::load`].
+    async fn save_assignments(
+        &mut self,
+        assignments: &[(StudentId, ProjectId)],
+        unassigned: &[StudentId],
+    ) -> eyre::Result<()>;
+
+    /// Store the assignments of a committed run. Backends keeping a history
+    /// of runs also record the run itself.
+    async fn save_run(&mut self, run: &Run) -> eyre::Result<()> {
+        let (assigned, unassigned) = run.split();
+        self.save_assignments(&assigned, &unassigned).await
+    }
+
+    /// Problems found in the raw data by the latest [`Backend::load`],
+    /// which cannot be seen on the loaded students anymore.
+    fn load_problems(&self) -> Vec<Problem> {
+        Vec::new()
+    }
+
+    /// Cutoff applied by the latest [`Backend::load`], if any.
+    fn cutoff(&self) -> Option<Cutoff> {
+        None
+    }
+}
+
+/// Any of the available backends, chosen at runtime.
+pub enum AnyBackend {
+    Csv(CsvDirectory),
+    Database(Box<Loader>),
+    Memory(MemoryBackend),
+}
+
+impl Backend for AnyBackend {
+    async fn load(&mut self) -> eyre::Result<(Vec<Student>, Vec<Project>)> {
+        match self {
+            AnyBackend::Csv(backend) => backend.load().await,
+            AnyBackend::Database(backend) => backend.load().await,
+            AnyBackend::Memory(backend) => backend.load().await,
+        }
+    }
+
+    async fn load_assignments(&mut self) -> eyre::Result<Vec<(StudentId, ProjectId)>> {
+        match self {
+            AnyBackend::Csv(backend) => backend.load_assignments().await,
+            AnyBackend::Database(backend) => backend.load_assignments().await,
+            AnyBackend::Memory(backend) => backend.load_assignments().await,
+        }
+    }
+
+    async fn save_assignments(
+        &mut self,
+        assignments: &[(StudentId, ProjectId)],
+        unassigned: &[StudentId],
+    ) -> eyre::Result<()> {
+        match self {
+            AnyBackend::Csv(backend) => backend.save_assignments(assignments, unassigned).await,
+            AnyBackend::Database(backend) => {
+                backend.save_assignments(assignments, unassigned).await
+            }
+            AnyBackend::Memory(backend) => backend.save_assignments(assignments, unassigned).await,
+        }
+    }
+
+    async fn save_run(&mut self, run: &Run) -> eyre::Result<()> {
+        match self {
+            AnyBackend::Csv(backend) => backend.save_run(run).await,
+            AnyBackend::Database(backend) => backend.save_run(run).await,
+            AnyBackend::Memory(backend) => backend.save_run(run).await,
+        }
+    }
+
+    fn load_problems(&self) -> Vec<Problem> {
+        match self {
+            AnyBackend::Csv(backend) => backend.load_problems(),
+            AnyBackend::Database(backend) => backend.load_problems(),
+            AnyBackend::Memory(backend) => backend.load_problems(),
+        }
+    }
+
+    fn cutoff(&self) -> Option<Cutoff> {
+        match self {
+            AnyBackend::Csv(backend) => backend.cutoff(),
+            AnyBackend::Database(backend) => backend.cutoff(),
+            AnyBackend::Memory(backend) => backend.cutoff(),
+        }
+    }
+}
+
+/// Attach preferences and bonuses, given as `(student, project, weight)`
+/// rows, to students as they are read. Rows of a given student must come by
+/// increasing weight: preferences with a smaller weight come first in
+/// rankings, and consecutive preferences with the same weight are put in the
+/// same tier. Bonuses are stored with the opposite sign of their weight. Rows
+/// referencing unknown students are reported as problems.
+struct Attacher<'a> {
+    students: &'a mut [Student],
+    index: HashMap<StudentId, usize>,
+    last_weights: Vec<Option<i64>>,
+    problems: Vec<Problem>,
+}
+
+impl<'a> Attacher<'a> {
+    fn new(students: &'a mut [Student]) -> Self {
+        let index = students
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.id, i))
+            .collect();
+        Self {
+            last_weights: vec![None; students.len()],
+            students,
+            index,
+            problems: Vec::new(),
+        }
+    }
+
+    fn preference(&mut self, (student, project, weight): WeightRow) {
+        let Some(&i) = self.index.get(&student) else {
+            self.problems
+                .push(Problem::UnknownPreferenceStudent { student, project });
+            return;
+        };
+        let rankings = &mut self.students[i].rankings;
+        match rankings.last_mut() {
+            Some(tier) if self.last_weights[i] == Some(weight) => tier.push(project),
+            _ => rankings.push(vec![project]),
+        }
+        self.last_weights[i] = Some(weight);
+    }
+
+    fn bonus(&mut self, (student, project, weight): WeightRow) {
+        match self.index.get(&student) {
+            Some(&i) => {
+                self.students[i].bonuses.insert(project, -weight);
+            }
+            None => self
+                .problems
+                .push(Problem::UnknownBonusStudent { student, project }),
+        }
+    }
+
+    /// Return the problems found in the rows.
+    fn finish(self, projects: &[Project]) -> Vec<Problem> {
+        let names = projects
+            .iter()
+            .map(|p| (p.id, p.name.as_str()))
+            .collect::<HashMap<_, _>>();
+        for student in self.students.iter().filter(|s| !s.bonuses.is_empty()) {
+            trace!(
+                student = %student,
+                bonuses = ?student
+                    .bonuses
+                    .iter()
+                    .map(|(p, w)| (names.get(p).copied().unwrap_or("?"), w))
+                    .collect::<Vec<_>>(),
+                "student has been assigned bonuses",
+            );
+        }
+        self.problems
+    }
+}
@@ -0,0 +1,231 @@
+//! Mapping of the database tables and columns used by the SQL loader.
+//! Every name can be overridden from the `[schema]` section of the
+//! configuration file, and defaults to the historical schema.
+//!
+//! The `campaign` columns, holding the campaign name as text, are only
+//! used when a campaign is configured. The project information columns
+//! are optional, and an empty name means that the column does not exist.
+
+use serde::Deserialize;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Schema {
+    pub projects: ProjectsTable,
+    pub students: StudentsTable,
+    pub preferences: PreferencesTable,
+    pub bonuses: BonusesTable,
+    pub assignments: AssignmentsTable,
+    pub history: HistoryTables,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct ProjectsTable {
+    pub table: String,
+    pub id: String,
+    pub name: String,
+    pub min_students: String,
+    pub max_students: String,
+    pub occurrences: String,
+    pub url: String,
+    pub supervisor: String,
+    pub supervisor_email: String,
+    pub description: String,
+    pub campaign: String,
+}
+
+impl ProjectsTable {
+    /// Existing project information columns, as `(alias, column)` pairs.
+    pub fn info_columns(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("url", &self.url),
+            ("supervisor", &self.supervisor),
+            ("supervisor_email", &self.supervisor_email),
+            ("description", &self.description),
+        ]
+        .into_iter()
+        .filter(|(_, column)| !column.is_empty())
+        .map(|(alias, column)| (alias, column.as_str()))
+        .collect()
+    }
+}
+
+impl Default for ProjectsTable {
+    fn default() -> Self {
+        Self {
+            table: "projets".into(),
+            id: "id".into(),
+            name: "intitule".into(),
+            min_students: "quota_min".into(),
+            max_students: "quota_max".into(),
+            occurrences: "occurrences".into(),
+            url: "url".into(),
+            supervisor: String::new(),
+            supervisor_email: String::new(),
+            description: String::new(),
+            campaign: "campagne".into(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct StudentsTable {
+    pub table: String,
+    pub id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub login: String,
+    pub email: String,
+    pub campaign: String,
+}
+
+impl StudentsTable {
+    /// Existing student contact columns, as `(alias, column)` pairs.
+    pub fn contact_columns(&self) -> Vec<(&'static str, &str)> {
+        [("login", &self.login), ("email", &self.email)]
+            .into_iter()
+            .filter(|(_, column)| !column.is_empty())
+            .map(|(alias, column)| (alias, column.as_str()))
+            .collect()
+    }
+}
+
+impl Default for StudentsTable {
+    fn default() -> Self {
+        Self {
+            table: "eleves".into(),
+            id: "id".into(),
+            first_name: "prenom".into(),
+            last_name: "nom".into(),
+            login: "login".into(),
+            email: "email".into(),
+            campaign: "campagne".into(),
+        }
+    }
+}
+
+/// Preferences are `(student, project, weight)` rows, dated with
+/// the `date` column.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct PreferencesTable {
+    pub table: String,
+    pub student: String,
+    pub project: String,
+    pub weight: String,
+    pub date: String,
+}
+
+impl Default for PreferencesTable {
+    fn default() -> Self {
+        Self {
+            table: "preferences".into(),
+            student: "eleve_id".into(),
+            project: "projet_id".into(),
+            weight: "poids".into(),
+            date: "date".into(),
+        }
+    }
+}
+
+/// Bonuses are `(student, project, weight)` rows, dated with the
+/// `date` column. Undated bonuses are always taken into account.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct BonusesTable {
+    pub table: String,
+    pub student: String,
+    pub project: String,
+    pub weight: String,
+    pub date: String,
+}
+
+impl Default for BonusesTable {
+    fn default() -> Self {
+        Self {
+            table: "pref_override".into(),
+            student: "eleve_id".into(),
+            project: "projet_id".into(),
+            weight: "poids".into(),
+            date: "date".into(),
+        }
+    }
+}
+
+/// Write-back target for the assignments: the `project` column of the
+/// row whose `student` column matches the student id is updated.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct AssignmentsTable {
+    pub table: String,
+    pub student: String,
+    pub project: String,
+    pub campaign: String,
+}
+
+impl Default for AssignmentsTable {
+    fn default() -> Self {
+        Self {
+            table: "eleves".into(),
+            student: "id".into(),
+            project: "attribution".into(),
+            campaign: "campagne".into(),
+        }
+    }
+}
+
+/// Tables keeping the history of committed runs, which are only written
+/// when enabled as they do not exist in the historical schema. Unless
+/// configured, history is enabled when the runs table exists, as in
+/// databases created by `init-db`. Their columns are not configurable:
+///
+/// - runs: `id`, `date`, `campagne`, `config` and `stats`
+/// - assignments: `historique_id`, `eleve_id`, `projet_id` and `rang`,
+///   which is 1-based and `NULL` for projects not ranked by the student
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct HistoryTables {
+    pub enabled: Option<bool>,
+    pub runs: String,
+    pub assignments: String,
+}
+
+impl HistoryTables {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled == Some(true)
+    }
+}
+
+impl Default for HistoryTables {
+    fn default() -> Self {
+        Self {
+            enabled: None,
+            runs: "historique".into(),
+            assignments: "historique_attributions".into(),
+        }
+    }
+}
+
+#[test]
+fn test_partial_override() {
+    let schema: Schema = toml::from_str(
+        r#"
+        [students]
+        table = "students"
+        last_name = "surname"
+
+        [bonuses]
+        weight = "bonus"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(schema.students.table, "students");
+    assert_eq!(schema.students.first_name, "prenom");
+    assert_eq!(schema.students.last_name, "surname");
+    assert_eq!(schema.bonuses.table, "pref_override");
+    assert_eq!(schema.bonuses.weight, "bonus");
+    assert_eq!(schema.preferences.table, "preferences");
+    assert_eq!(schema.assignments.project, "attribution");
+}
@@ -0,0 +1,53 @@
+CREATE TABLE projets (
+  id INTEGER NOT NULL PRIMARY KEY,
+  intitule TEXT NOT NULL,
+  quota_min INTEGER NOT NULL,
+  quota_max INTEGER NOT NULL,
+  occurrences INTEGER NOT NULL,
+  url TEXT,
+  campagne TEXT DEFAULT NULL
+);
+
+CREATE TABLE eleves (
+  id INTEGER NOT NULL PRIMARY KEY,
+  login TEXT,
+  prenom TEXT NOT NULL,
+  nom TEXT NOT NULL,
+  attribution INTEGER DEFAULT NULL,
+  email TEXT DEFAULT NULL,
+  campagne TEXT DEFAULT NULL
+);
+
+CREATE TABLE preferences (
+  eleve_id INTEGER NOT NULL,
+  projet_id INTEGER NOT NULL,
+  poids INTEGER NOT NULL,
+  date TEXT DEFAULT NULL,
+  "index" INTEGER PRIMARY KEY AUTOINCREMENT
+);
+
+CREATE TABLE pref_override (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  eleve_id INTEGER NOT NULL,
+  projet_id INTEGER NOT NULL,
+  poids INTEGER NOT NULL,
+  date TEXT DEFAULT NULL
+);
+
+CREATE INDEX idx_eleve_proj ON pref_override (eleve_id, projet_id);
+
+CREATE TABLE historique (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  campagne TEXT DEFAULT NULL,
+  config TEXT NOT NULL,
+  stats TEXT NOT NULL
+);
+
+CREATE TABLE historique_attributions (
+  historique_id INTEGER NOT NULL,
+  eleve_id INTEGER NOT NULL,
+  projet_id INTEGER DEFAULT NULL,
+  rang INTEGER DEFAULT NULL,
+  PRIMARY KEY (historique_id, eleve_id)
+);
@@ -0,0 +1,262 @@
+use super::{Attacher, WeightRow};
+use crate::model::{Project, ProjectId, ProjectInfo, Student, StudentId};
+use calamine::{Data, Range, Reader, open_workbook_auto};
+use eyre::{Context, OptionExt, eyre};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Load students, projects and preferences from a workbook (ODS, XLSX
+/// or XLS) containing two sheets, each starting with a header row:
+///
+/// - `projects`: `id`, `intitule`, `quota_min`, `quota_max`, `occurrences`,
+///   and optionally `url`, `encadrant`, `encadrant_email` and `description`
+/// - `preferences`: `id`, `prenom`, `nom`, optionally `login` and `email`,
+///   and one column per project whose header is the project id. Cells hold the rank given
+///   by the student to the project, starting at 1, and may be left empty.
+///   Projects given the same rank are tied.
+pub fn load_workbook(file_name: &Path) -> eyre::Result<(Vec<Student>, Vec<Project>)> {
+    let mut workbook = open_workbook_auto(file_name)
+        .context(format!("cannot open workbook `{}`", file_name.display()))?;
+    let mut open = |name| -> eyre::Result<Sheet> {
+        Ok(Sheet {
+            name,
+            range: workbook
+                .worksheet_range(name)
+                .context(format!("cannot read sheet `{name}`"))?,
+        })
+    };
+    let projects = read_projects(&open("projects")?)?;
+    let (mut students, preferences) = read_preferences(&open("preferences")?)?;
+    let mut attacher = Attacher::new(&mut students);
+    for row in preferences {
+        attacher.preference(row);
+    }
+    attacher.finish(&projects);
+    Ok((students, projects))
+}
+
+fn read_projects(sheet: &Sheet) -> eyre::Result<Vec<Project>> {
+    let [id, name, min, max, occurrences] =
+        ["id", "intitule", "quota_min", "quota_max", "occurrences"].map(|h| sheet.column(h));
+    let (id, name, min, max, occurrences) = (id?, name?, min?, max?, occurrences?);
+    let [url, supervisor, supervisor_email, description] =
+        ["url", "encadrant", "encadrant_email", "description"].map(|h| sheet.column(h).ok());
+    sheet
+        .rows()
+        .filter(|&row| !sheet.is_empty((row, id)))
+        .map(|row| {
+            let text = |col| sheet.optional_text(row, col);
+            Ok(Project {
+                id: ProjectId(sheet.number((row, id))?),
+                name: sheet.text((row, name)),
+                min_students: sheet.number((row, min))?,
+                max_students: sheet.number((row, max))?,
+                max_occurrences: sheet.number((row, occurrences))?,
+                info: ProjectInfo {
+                    url: text(url),
+                    supervisor: text(supervisor),
+                    supervisor_email: text(supervisor_email),
+                    description: text(description),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Read the students and their `(student, project, rank)` preferences,
+/// ordered by student and rank.
+fn read_preferences(sheet: &Sheet) -> eyre::Result<(Vec<Student>, Vec<WeightRow>)> {
+    let [id, first_name, last_name] = ["id", "prenom", "nom"].map(|h| sheet.column(h));
+    let (id, first_name, last_name) = (id?, first_name?, last_name?);
+    let [login, email] = ["login", "email"].map(|h| sheet.column(h).ok());
+    let projects = (0..sheet.range.width())
+        .filter(|&col| {
+            ![Some(id), Some(first_name), Some(last_name), login, email].contains(&Some(col))
+                && !sheet.is_empty((0, col))
+        })
+        .map(|col| Ok((col, ProjectId(sheet.number((0, col))?))))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let mut students = Vec::new();
+    let mut preferences = Vec::new();
+    for row in sheet.rows().filter(|&row| !sheet.is_empty((row, id))) {
+        let student = StudentId(sheet.number((row, id))?);
+        let text = |col| sheet.optional_text(row, col);
+        students.push(Student {
+            login: text(login),
+            email: text(email),
+            ..Student::new(
+                student,
+                sheet.text((row, first_name)),
+                sheet.text((row, last_name)),
+                Vec::new(),
+                HashMap::new(),
+            )
+        });
+        let mut ranks = Vec::new();
+        for &(col, project) in &projects {
+            if !sheet.is_empty((row, col)) {
+                ranks.push((student, project, sheet.number((row, col))?));
+            }
+        }
+        ranks.sort_by_key(|&(_, _, rank)| rank);
+        preferences.extend(ranks);
+    }
+    Ok((students, preferences))
+}
+
+/// A named sheet of the workbook. Positions are `(row, column)` relative
+/// to the start of the range, the header row being row 0.
+struct Sheet {
+    name: &'static str,
+    range: Range<Data>,
+}
+
+impl Sheet {
+    /// Data rows, following the header row.
+    fn rows(&self) -> impl Iterator<Item = usize> + use<> {
+        1..self.range.height()
+    }
+
+    /// Position of the column whose header is `header`.
+    fn column(&self, header: &str) -> eyre::Result<usize> {
+        (0..self.range.width())
+            .find(|&col| self.text((0, col)) == header)
+            .ok_or_eyre(format!("no `{header}` column in sheet `{}`", self.name))
+    }
+
+    fn is_empty(&self, pos: (usize, usize)) -> bool {
+        self.text(pos).is_empty()
+    }
+
+    fn text(&self, pos: (usize, usize)) -> String {
+        match self.range.get(pos) {
+            None | Some(Data::Empty) => String::new(),
+            Some(data) => data.to_string().trim().to_owned(),
+        }
+    }
+
+    /// Text of the cell in the given row of an optional column, if the
+    /// column exists and the cell is not empty.
+    fn optional_text(&self, row: usize, col: Option<usize>) -> Option<String> {
+        col.map(|col| self.text((row, col)))
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Parse an integer, which may be stored as a floating point number
+    /// or as text in the cell.
+    fn number<T: TryFrom<i64>>(&self, pos: (usize, usize)) -> eyre::Result<T> {
+        let value = match self.range.get(pos) {
+            Some(Data::Int(n)) => Some(*n),
+            Some(Data::Float(f)) if f.fract() == 0.0 => Some(*f as i64),
+            Some(Data::String(s)) => s.trim().parse().ok(),
+            _ => None,
+        };
+        value.and_then(|n| T::try_from(n).ok()).ok_or_else(|| {
+            eyre!(
+                "invalid number `{}` in cell {} of sheet `{}`",
+                self.text(pos),
+                self.cell_name(pos),
+                self.name
+            )
+        })
+    }
+
+    /// Name of the cell in the `A1` notation.
+    fn cell_name(&self, (row, col): (usize, usize)) -> String {
+        let (start_row, start_col) = self.range.start().unwrap_or_default();
+        let (row, mut col) = (start_row as usize + row, start_col as usize + col);
+        let mut letters = Vec::new();
+        loop {
+            letters.push(char::from(b'A' + (col % 26) as u8));
+            if col < 26 {
+                break;
+            }
+            col = col / 26 - 1;
+        }
+        format!("{}{}", letters.iter().rev().collect::<String>(), row + 1)
+    }
+}
+
+#[test]
+fn test_load_workbook() {
+    let file_name = std::env::temp_dir().join(format!("rsolver-{}.xlsx", std::process::id()));
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    let sheet = workbook.add_worksheet().set_name("projects").unwrap();
+    for (col, header) in [
+        "id",
+        "intitule",
+        "quota_min",
+        "quota_max",
+        "occurrences",
+        "url",
+    ]
+    .iter()
+    .enumerate()
+    {
+        sheet.write(0, col as u16, *header).unwrap();
+    }
+    for (row, (id, name)) in [(3, "Robots"), (7, "Compilers")].iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write(row, 0, *id).unwrap();
+        sheet.write(row, 1, *name).unwrap();
+        sheet.write(row, 2, 1).unwrap();
+        sheet.write(row, 3, 3).unwrap();
+        sheet.write(row, 4, 2).unwrap();
+    }
+    sheet.write(1, 5, "https://example.com/robots").unwrap();
+    let sheet = workbook.add_worksheet().set_name("preferences").unwrap();
+    for (col, header) in ["id", "prenom", "nom", "7", "login", "3"]
+        .iter()
+        .enumerate()
+    {
+        sheet.write(0, col as u16, *header).unwrap();
+    }
+    sheet.write(1, 0, 12).unwrap();
+    sheet.write(1, 1, "Ada").unwrap();
+    sheet.write(1, 2, "Lovelace").unwrap();
+    sheet.write(1, 3, 2).unwrap();
+    sheet.write(1, 4, "alovelace").unwrap();
+    sheet.write(1, 5, 1).unwrap();
+    sheet.write(2, 0, 15).unwrap();
+    sheet.write(2, 1, "Alan").unwrap();
+    sheet.write(2, 2, "Turing").unwrap();
+    sheet.write(2, 3, 1).unwrap();
+    sheet.write(2, 5, 1).unwrap();
+    sheet.write(3, 0, 18).unwrap();
+    sheet.write(3, 1, "Grace").unwrap();
+    sheet.write(3, 2, "Hopper").unwrap();
+    sheet.write(3, 3, 1).unwrap();
+    workbook.save(&file_name).unwrap();
+    let (students, projects) = load_workbook(&file_name).unwrap();
+    std::fs::remove_file(&file_name).unwrap();
+    assert_eq!(projects.len(), 2);
+    assert_eq!(projects[1].name, "Compilers");
+    assert_eq!(projects[1].max_occurrences, 2);
+    assert_eq!(
+        projects[0].info.url.as_deref(),
+        Some("https://example.com/robots")
+    );
+    assert_eq!(projects[1].info.url, None);
+    assert_eq!(students.len(), 3);
+    assert_eq!(students[0].name, "Ada Lovelace");
+    assert_eq!(students[0].login.as_deref(), Some("alovelace"));
+    assert_eq!(students[1].login, None);
+    assert_eq!(
+        students[0].rankings,
+        vec![vec![ProjectId(3)], vec![ProjectId(7)]]
+    );
+    assert_eq!(students[1].rankings, vec![vec![ProjectId(7), ProjectId(3)]]);
+    assert_eq!(students[2].rankings, vec![vec![ProjectId(7)]]);
+}
+
+#[test]
+fn test_cell_name() {
+    let sheet = Sheet {
+        name: "projects",
+        range: Range::new((0, 0), (2, 30)),
+    };
+    assert_eq!(sheet.cell_name((0, 0)), "A1");
+    assert_eq!(sheet.cell_name((2, 25)), "Z3");
+    assert_eq!(sheet.cell_name((1, 27)), "AB2");
+    assert!(sheet.number::<usize>((1, 1)).is_err());
+}
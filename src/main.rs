@@ -1,38 +1,143 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::cast_possible_truncation)]
 
-use crate::model::Assignments;
+use crate::loaders::{
+    AnyBackend, Backend, CsvDirectory, Cutoff, Filter, Loader, MemoryBackend, load_workbook,
+};
+use crate::model::{Assignments, Student};
+use crate::snapshot::Snapshot;
+use crate::timestamp::Timestamp;
 use clap::{
     ArgAction::{Count, SetFalse, SetTrue},
-    Parser,
+    Parser, Subcommand, ValueEnum,
 };
-use eyre::{Context, ensure};
+use eyre::{Context, OptionExt, bail, ensure};
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use tracing::Level;
+use tracing::{Level, info, warn};
 
+mod artifacts;
 mod checks;
 mod display;
+mod explain;
+mod html;
 mod hungarian;
 mod loaders;
 mod model;
+mod notify;
 mod remap;
+mod report;
+mod snapshot;
+mod solver;
 mod stats;
+#[cfg(test)]
+mod testing;
+mod timestamp;
+mod validation;
 
 #[derive(Parser)]
 #[clap(version, author, about)]
 #[allow(clippy::struct_excessive_bools)]
 struct Options {
     /// Use FILE instead of rsolver.ini
+    ///
+    /// When data comes from CSV files, a snapshot or a workbook, the
+    /// default configuration is used unless FILE is explicitly given.
     #[clap(short, long, value_parser)]
     config: Option<PathBuf>,
     /// Output assignments as CSV records
     ///
     /// The CSV records will be output on the standard output instead
-    /// of the plain text assignment.
+    /// of the plain text assignment. This is the same as `--format csv`.
     #[clap(short = 'C', long, action = SetTrue)]
     csv: bool,
+    /// Columns of the CSV assignments, separated by commas
+    ///
+    /// This overrides the `columns` entry of the `[csv]` section of the
+    /// configuration file. It requires CSV output or `--output-dir`.
+    #[clap(long, value_enum, value_delimiter = ',', value_name = "COLUMNS")]
+    columns: Vec<display::Column>,
+    /// Output format on the standard output
+    ///
+    /// The JSON document holds the assignments, statistics and remarks
+    /// found in the plain text output.
+    #[clap(long, value_enum, default_value_t = Format::Text, conflicts_with = "csv")]
+    format: Format,
+    /// Explain why students did not get a better ranked project
+    ///
+    /// For every project ranked better than the one the student got, tell
+    /// whether it has been cancelled, has not been opened, or which students
+    /// took its seats. Only the students given with `--student` are
+    /// explained if any.
+    #[clap(long, action = SetTrue)]
+    explain: bool,
+    /// Also write the report as a standalone HTML document into FILE
+    #[clap(long, value_name = "FILE")]
+    html: Option<PathBuf>,
+    /// Also write every report into separate files in DIR
+    ///
+    /// DIR receives the plain-text report in `report.txt` and its HTML
+    /// version in `report.html`, the explanation of every student result in
+    /// `explanations.txt`, the assignments
+    /// in `assignments.csv`, the roster of every open project in
+    /// `projects/`, the roster of every occurrence with contact details in
+    /// `rosters/`, the unassigned and unregistered students in
+    /// `unassigned.csv` and the useless bonuses in `missed_bonuses.csv`.
+    /// It cannot be the directory given with `--csv-dir`.
+    #[clap(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+    /// Write a notification message for every assigned student into the
+    /// maildir DIR
+    ///
+    /// Messages are built from the `[notify]` section of the configuration
+    /// file. They are only written once the assignments have been checked.
+    #[clap(long, value_name = "DIR")]
+    maildir: Option<PathBuf>,
+    /// Send a notification message to every assigned student
+    ///
+    /// Messages are built from the `[notify]` section of the configuration
+    /// file and sent through the SMTP server of its `[notify.smtp]` section
+    /// once the assignments have been committed and checked. It cannot be
+    /// used with `--snapshot` or `--workbook`, as the assignments are not
+    /// stored anywhere.
+    #[clap(long, action = SetTrue)]
+    send_mail: bool,
+    /// Use CSV files from DIR instead of the database
+    ///
+    /// DIR must contain `projects.csv`, `students.csv`, `preferences.csv`
+    /// and optionally `bonuses.csv`. Assignments are written into
+    /// `assignments.csv` in the same directory.
+    #[clap(long, value_name = "DIR")]
+    csv_dir: Option<PathBuf>,
+    /// Use the problem instance stored in FILE instead of the database
+    ///
+    /// The configuration stored in the snapshot is used, and the
+    /// configuration file is only read if explicitly given.
+    /// Assignments are not written anywhere.
+    #[clap(long, value_name = "FILE", conflicts_with = "csv_dir")]
+    snapshot: Option<PathBuf>,
+    /// Use the ODS or XLSX workbook FILE instead of the database
+    ///
+    /// The `projects` sheet has `id`, `intitule`, `quota_min`, `quota_max`
+    /// and `occurrences` columns. The `preferences` sheet has one row per
+    /// student with `id`, `prenom` and `nom` columns, followed by one
+    /// column per project id holding the rank given by the student.
+    /// Assignments are not written anywhere.
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["csv_dir", "snapshot"])]
+    workbook: Option<PathBuf>,
+    /// Ignore preferences and bonuses dated after TIMESTAMP
+    ///
+    /// TIMESTAMP is given as `YYYY-MM-DD HH:MM:SS`. If only a date is
+    /// given, rows dated on that day are still taken into account.
+    /// Undated rows are always taken into account. Snapshots and
+    /// workbooks hold undated rows only. The number of ignored rows is
+    /// shown in the report and recorded with committed runs.
+    #[clap(long, value_name = "TIMESTAMP", conflicts_with_all = ["snapshot", "workbook"])]
+    as_of: Option<Timestamp>,
+    /// Export the loaded problem instance and configuration to FILE
+    #[clap(long, value_name = "FILE")]
+    export_snapshot: Option<PathBuf>,
     /// Do not assign unregistered students to any project
     ///
     /// Unregistered students will be dropped from the system.
@@ -41,7 +146,7 @@ struct Options {
     #[clap(short, long, action = SetTrue)]
     drop_unregistered: bool,
     /// Do not write back results to database
-    #[clap(short = 'n', long = "dry_run", action = SetFalse)]
+    #[clap(short = 'n', long = "dry_run", action = SetFalse, global = true)]
     commit_to_db: bool,
     /// Rename lazy student into Zzz + order
     #[clap(short, long, action = SetTrue)]
@@ -51,17 +156,75 @@ struct Options {
     /// This option can be repeated.
     #[clap(short, action = Count)]
     verbosity: u8,
+    /// Only display the assignment of the student whose login is LOGIN
+    ///
+    /// This option can be repeated.
+    #[clap(long, value_name = "LOGIN")]
+    student: Vec<String>,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Csv,
+    Json,
 }
 
-#[derive(Deserialize)]
+#[derive(Subcommand)]
+enum Command {
+    /// Create the database schema in a new SQLite database
+    ///
+    /// If `--snapshot`, `--csv-dir` or `--workbook` is given, the problem instance
+    /// is imported into the newly created database.
+    #[cfg(feature = "sqlite")]
+    InitDb {
+        /// SQLite database file to create
+        database: PathBuf,
+    },
+    /// List the runs recorded in history, or restore one of them
+    ///
+    /// Without RUN, the committed runs are listed. With RUN, the changes
+    /// from the stored assignments are shown, then the assignments of the
+    /// run are stored back unless `--dry_run` is given or more students
+    /// than `max_changes` would change. History must not be disabled in
+    /// the `[schema.history]` section.
+    Rollback {
+        /// Identifier of the run to restore
+        run: Option<i64>,
+    },
+}
+
+#[derive(Default, Deserialize)]
 pub struct Config {
+    #[serde(default)]
     pub solver: SolverConfig,
+    #[serde(default)]
     pub hungarian: hungarian::Config,
+    #[serde(default)]
+    pub schema: loaders::Schema,
+    #[serde(default)]
+    pub notify: notify::Config,
+    #[serde(default)]
+    pub csv: display::CsvConfig,
 }
 
-#[derive(Deserialize)]
+#[derive(Default, Deserialize)]
 pub struct SolverConfig {
-    pub database: String,
+    pub database: Option<String>,
+    /// Only consider rows belonging to this campaign
+    pub campaign: Option<String>,
+    /// Refuse to commit if more students than this would change
+    pub max_changes: Option<usize>,
+}
+
+impl SolverConfig {
+    fn database(&self) -> eyre::Result<&str> {
+        self.database
+            .as_deref()
+            .ok_or_eyre("no database configured in the `[solver]` section")
+    }
 }
 
 impl Config {
@@ -77,6 +240,84 @@ impl Config {
     }
 }
 
+fn filter(options: &Options) -> Filter {
+    Filter {
+        as_of: options.as_of.clone(),
+        campaign: None,
+    }
+}
+
+/// Check whether data comes from files rather than from the database.
+fn from_files(options: &Options) -> bool {
+    options.csv_dir.is_some() || options.snapshot.is_some() || options.workbook.is_some()
+}
+
+/// Return the backend to use if data comes from files rather than
+/// from the database.
+fn file_backend(options: &Options, snapshot: Option<Snapshot>) -> eyre::Result<Option<AnyBackend>> {
+    Ok(if let Some(snapshot) = snapshot {
+        Some(AnyBackend::Memory(MemoryBackend::new(
+            snapshot.students,
+            snapshot.projects,
+        )))
+    } else if let Some(file_name) = &options.workbook {
+        let (students, projects) = load_workbook(file_name)?;
+        Some(AnyBackend::Memory(MemoryBackend::new(students, projects)))
+    } else {
+        options
+            .csv_dir
+            .as_ref()
+            .map(|dir| AnyBackend::Csv(CsvDirectory::new(dir, filter(options))))
+    })
+}
+
+/// Create a new SQLite database, and import data from `source` if any.
+#[cfg(feature = "sqlite")]
+async fn init_db(database: &Path, source: Option<AnyBackend>) -> eyre::Result<()> {
+    let mut loader = Loader::create_sqlite(database).await?;
+    if let Some(mut source) = source {
+        let (students, projects) = source.load().await?;
+        loader.import(&students, &projects).await?;
+    }
+    Ok(())
+}
+
+/// Load the configuration, and apply the command line overrides and the
+/// parameters of the snapshot, if any.
+fn load_config(options: &Options, snapshot: Option<&Snapshot>) -> eyre::Result<Config> {
+    let mut config = match &options.config {
+        Some(path) => Config::load(path.clone())?,
+        None if from_files(options) => Config::default(),
+        None => Config::load(PathBuf::from_str("rsolver.ini")?)?,
+    };
+    if let Some(snapshot) = snapshot {
+        config.hungarian = snapshot.hungarian.clone();
+    }
+    if !options.columns.is_empty() {
+        ensure!(
+            format(options) == Format::Csv || options.output_dir.is_some(),
+            "`--columns` is only used with CSV output or `--output-dir`"
+        );
+        config.csv.columns = Some(options.columns.clone());
+    }
+    config.csv.check()?;
+    ensure!(
+        config.solver.campaign.is_none() || !from_files(options),
+        "campaigns are only supported when loading from a database"
+    );
+    // The reported assignments would replace the ones stored by the backend
+    if let (Some(csv_dir), Some(output_dir)) = (&options.csv_dir, &options.output_dir) {
+        ensure!(
+            !matches!(
+                (csv_dir.canonicalize(), output_dir.canonicalize()),
+                (Ok(a), Ok(b)) if a == b
+            ),
+            "`--output-dir` cannot be the directory given with `--csv-dir`"
+        );
+    }
+    Ok(config)
+}
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     color_eyre::install()?;
@@ -89,69 +330,303 @@ async fn main() -> eyre::Result<()> {
         _ => Level::TRACE,
     };
     tracing_subscriber::fmt::fmt().with_max_level(level).init();
-    let config = Config::load(options.config.unwrap_or(PathBuf::from_str("rsolver.ini")?))?;
-    let mut loader = loaders::Loader::new(&config.solver.database).await?;
-    // Load data from the database
-    let (original_students, original_projects) = loader.load().await?;
-    // Isolate lazy students before remapping if asked to do so
-    let (original_students, lazy_students) = if options.drop_unregistered {
-        remap::separate_lazy(original_students)
-    } else {
-        (original_students, vec![])
-    };
-    // Remap students and projects into contiguous values for the algorithm sake
-    let (students, projects) = {
-        let (mut students, mut projects) = (original_students.clone(), original_projects.clone());
-        // Work with normalized values (students and projets starting at 0 and without gaps)
-        remap::remap(&mut students, &mut projects);
-        (students, projects)
+    let snapshot = options.snapshot.as_ref().map(Snapshot::load).transpose()?;
+    #[cfg(feature = "sqlite")]
+    if let Some(Command::InitDb { database }) = &options.command {
+        return init_db(database, file_backend(&options, snapshot)?).await;
+    }
+    let config = load_config(&options, snapshot.as_ref())?;
+    if let Some(Command::Rollback { run }) = options.command {
+        ensure!(
+            !from_files(&options),
+            "runs can only be restored into a database"
+        );
+        return rollback(&config, &options, run).await;
+    }
+    let notifier = notifier(&options, &config)?;
+    let mut backend = match file_backend(&options, snapshot)? {
+        Some(backend) => backend,
+        None => AnyBackend::Database(Box::new(
+            Loader::new(
+                config.solver.database()?,
+                config.schema.clone(),
+                Filter {
+                    campaign: config.solver.campaign.clone(),
+                    ..filter(&options)
+                },
+            )
+            .await?,
+        )),
     };
-    // Compute the new assignments
-    let mut assignments = Assignments::new(students, projects);
-    hungarian::assign(&mut assignments, &config.hungarian)?;
-    // Save the results if requested
-    if options.commit_to_db {
-        // Make a list of unassigned students, be it from the algorithm
-        // or because lazy students were singled out beforehand
-        let mut unassigned_students = assignments
-            .unassigned_students()
-            .iter()
-            .map(|s| original_students[s.0].id)
-            .collect::<Vec<_>>();
-        unassigned_students.append(&mut lazy_students.clone());
-        unassigned_students.sort();
-        // Other students, i.e. assigned students
-        let assignments = assignments
-            .filter_students(|s| unassigned_students.binary_search(&s).is_err())
-            .into_iter()
-            .map(|s| {
-                (
-                    original_students[s.0].id,
-                    original_projects[assignments.project_for(s).unwrap().0].id,
-                )
-            })
-            .collect::<Vec<_>>();
-        // Save the assignments and non-assignments into the database
-        loader
-            .save_assignments(&assignments, &unassigned_students)
-            .await?;
-    }
-    // If CSV output is requested, only output assignments
-    if options.csv {
-        display::display_csv(&assignments)?;
-    } else {
-        // Rename lazy students if requested, to ease output comparison
-        display::display_details(&assignments, options.rename_unregistered);
-        display::display_stats(&assignments, lazy_students.len());
-        display::display_missed_bonuses(&assignments);
-        display::display_empty(&assignments);
-        display::display_with_many_lazy(&assignments);
+    let (students, projects) = backend.load().await?;
+    if let Some(file_name) = &options.export_snapshot {
+        Snapshot::new(
+            students.clone(),
+            projects.clone(),
+            config.hungarian.resolved(),
+        )
+        .save(file_name)?;
+    }
+    validation::check(&students, &projects, backend.load_problems())?;
+    check_logins(&students, &options.student)?;
+    let everyone = (students.clone(), projects.clone());
+    let solver::Solution {
+        assignments,
+        lazy_students,
+        changes,
+    } = solver::solve(
+        &mut backend,
+        students,
+        projects,
+        &config.hungarian,
+        options.drop_unregistered,
+        options.commit_to_db,
+        config.solver.max_changes,
+    )
+    .await?;
+    // Changes are only listed along with text output
+    if let Some(changes) = &changes
+        && format(&options) == Format::Text
+    {
+        display::display_changes(changes, &everyone.0, &everyone.1);
     }
+    output(
+        &options,
+        &config,
+        &assignments,
+        &lazy_students,
+        backend.cutoff().as_ref(),
+    )?;
     checks::check_pinned_consistency(&assignments);
     ensure!(
         assignments.unassigned_students().is_empty(),
         "{n} students could not get assigned to any project",
         n = assignments.unassigned_students().len()
     );
-    checks::ensure_acceptable(&assignments)
+    checks::ensure_acceptable(&assignments)?;
+    if let Some(notifier) = notifier {
+        notify(&notifier, &options, &config.notify, &assignments).await?;
+    }
+    Ok(())
+}
+
+/// Return the notifier if notifications are requested, after checking
+/// that they can be built and sent.
+fn notifier(options: &Options, config: &Config) -> eyre::Result<Option<notify::Notifier>> {
+    if options.send_mail {
+        ensure!(
+            options.commit_to_db,
+            "messages can only be sent for committed assignments"
+        );
+        // The memory backend discards the assignments at exit
+        ensure!(
+            options.snapshot.is_none() && options.workbook.is_none(),
+            "messages cannot be sent as assignments are not stored with `--snapshot` or `--workbook`"
+        );
+        ensure!(
+            config.notify.smtp.is_some(),
+            "no SMTP server configured in the `[notify.smtp]` section"
+        );
+    }
+    (options.maildir.is_some() || options.send_mail)
+        .then(|| notify::Notifier::new(&config.notify))
+        .transpose()
+}
+
+/// Notify the assigned students of their project into the maildir and
+/// through SMTP as requested.
+async fn notify(
+    notifier: &notify::Notifier,
+    options: &Options,
+    config: &notify::Config,
+    assignments: &Assignments,
+) -> eyre::Result<()> {
+    let (messages, missing, invalid) = notifier.messages(assignments)?;
+    for s in missing {
+        warn!(
+            student = %assignments.student(s),
+            "student has no email address and will not be notified"
+        );
+    }
+    for s in invalid {
+        warn!(
+            student = %assignments.student(s),
+            email = assignments.student(s).email.as_deref().unwrap_or_default(),
+            "student has an invalid email address and will not be notified"
+        );
+    }
+    if let Some(dir) = &options.maildir {
+        notify::write_maildir(dir, &messages)?;
+    }
+    if options.send_mail
+        && let Some(smtp) = &config.smtp
+    {
+        notify::send(smtp, &messages).await?;
+        info!(messages = %messages.len(), "notification messages have been sent");
+    }
+    Ok(())
+}
+
+/// Check that every login designates exactly one student.
+fn check_logins(students: &[Student], logins: &[String]) -> eyre::Result<()> {
+    for login in logins {
+        match students
+            .iter()
+            .filter(|s| s.login.as_ref() == Some(login))
+            .count()
+        {
+            0 => bail!("unknown student login `{login}`"),
+            1 => (),
+            _ => bail!("student login `{login}` is ambiguous"),
+        }
+    }
+    Ok(())
+}
+
+/// Format of the standard output.
+fn format(options: &Options) -> Format {
+    if options.csv {
+        Format::Csv
+    } else {
+        options.format
+    }
+}
+
+/// Output the assignments on the standard output in the requested format,
+/// and into the requested files. `dropped` are the unregistered students
+/// which have been dropped from the system, which only appear in the
+/// artifacts, and `cutoff` the cutoff applied to preferences and bonuses.
+fn output(
+    options: &Options,
+    config: &Config,
+    assignments: &Assignments,
+    dropped: &[Student],
+    cutoff: Option<&Cutoff>,
+) -> eyre::Result<()> {
+    let eliminated = dropped.len();
+    let mut stdout = std::io::stdout().lock();
+    match format(options) {
+        Format::Csv if options.explain => bail!("explanations cannot be output as CSV"),
+        Format::Csv if !options.student.is_empty() => {
+            bail!("the assignment of given students cannot be output as CSV");
+        }
+        // If CSV output is requested, only output assignments
+        Format::Csv => display::display_csv(stdout, assignments, &config.csv)?,
+        Format::Json if options.explain => {
+            display::display_explanations_json(
+                &mut stdout,
+                &explanations(options, &config.hungarian, assignments),
+            )?;
+        }
+        Format::Json if !options.student.is_empty() => {
+            display::display_students_json(&mut stdout, assignments, &options.student)?;
+        }
+        Format::Json => {
+            display::display_json(
+                &mut stdout,
+                assignments,
+                options.rename_unregistered,
+                eliminated,
+                cutoff,
+            )?;
+        }
+        Format::Text if options.explain => {
+            display::display_explanations(
+                &mut stdout,
+                &explanations(options, &config.hungarian, assignments),
+            )?;
+        }
+        Format::Text if !options.student.is_empty() => {
+            display::display_students(assignments, &options.student);
+        }
+        Format::Text => {
+            // Rename lazy students if requested, to ease output comparison
+            display::display_report(
+                &mut stdout,
+                assignments,
+                options.rename_unregistered,
+                eliminated,
+                cutoff,
+            )?;
+        }
+    }
+    if let Some(file_name) = &options.html {
+        artifacts::write_html(
+            file_name,
+            assignments,
+            options.rename_unregistered,
+            eliminated,
+            cutoff,
+        )?;
+    }
+    if let Some(dir) = &options.output_dir {
+        artifacts::write_artifacts(
+            dir,
+            assignments,
+            dropped,
+            cutoff,
+            &config.hungarian,
+            &config.csv,
+            options.rename_unregistered,
+        )?;
+    }
+    Ok(())
+}
+
+/// Explanations of the students given with `--student`, or of every
+/// student if none is given. Students which are not part of the
+/// assignments have been dropped as unregistered.
+fn explanations(
+    options: &Options,
+    config: &hungarian::Config,
+    assignments: &Assignments,
+) -> Vec<explain::Explanation> {
+    if options.student.is_empty() {
+        return explain::explanations(assignments, config);
+    }
+    options
+        .student
+        .iter()
+        .filter_map(|login| {
+            let s = assignments
+                .all_students()
+                .into_iter()
+                .find(|&s| assignments.student(s).login.as_ref() == Some(login));
+            if s.is_none() {
+                warn!(%login, "unregistered student is not part of the assignments");
+            }
+            s.map(|s| explain::explanation(assignments, config, s))
+        })
+        .collect()
+}
+
+/// List the runs recorded in history if `run` is `None`, or restore the
+/// assignments of `run`.
+async fn rollback(config: &Config, options: &Options, run: Option<i64>) -> eyre::Result<()> {
+    let mut loader = Loader::new(
+        config.solver.database()?,
+        config.schema.clone(),
+        Filter {
+            campaign: config.solver.campaign.clone(),
+            ..filter(options)
+        },
+    )
+    .await?;
+    let Some(id) = run else {
+        display::display_runs(&loader.runs().await?);
+        return Ok(());
+    };
+    let (students, projects) = loader.load().await?;
+    let (run, stored) = loader
+        .restore_run(id, options.commit_to_db, |run, stored| {
+            // Same guard as when committing a new run
+            let changes = (!stored.is_empty()).then(|| solver::changes(stored, run));
+            solver::check_changes(changes.as_deref(), config.solver.max_changes)
+        })
+        .await?;
+    display::display_changes(&solver::changes(&stored, &run), &students, &projects);
+    if options.commit_to_db {
+        info!(run = %id, "assignments of the run have been restored");
+    }
+    Ok(())
 }
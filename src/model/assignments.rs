@@ -12,6 +12,10 @@ pub struct Assignments {
     assigned_to: Vec<Option<ProjectId>>,
     assigned: Vec<Vec<StudentId>>,
     pinned: Vec<Vec<StudentId>>,
+    previous: Vec<Option<ProjectId>>,
+    previous_occurrences: Vec<u32>,
+    original_student_ids: Vec<StudentId>,
+    original_project_ids: Vec<ProjectId>,
 }
 
 #[allow(dead_code)]
@@ -35,6 +39,8 @@ impl Assignments {
             })
             .collect();
         let max_occurrences = projects.iter().map(|p| p.max_occurrences).collect();
+        let original_student_ids = students.iter().map(|s| s.id).collect();
+        let original_project_ids = projects.iter().map(|p| p.id).collect();
         Assignments {
             students,
             projects,
@@ -42,6 +48,10 @@ impl Assignments {
             assigned_to: vec![None; number_of_students],
             assigned: vec![Vec::new(); number_of_projects],
             pinned,
+            previous: vec![None; number_of_students],
+            previous_occurrences: vec![0; number_of_projects],
+            original_student_ids,
+            original_project_ids,
         }
     }
 
@@ -81,7 +91,7 @@ impl Assignments {
             .collect()
     }
 
-    pub fn rankings(&self, student: StudentId) -> &Vec<ProjectId> {
+    pub fn rankings(&self, student: StudentId) -> &Vec<Vec<ProjectId>> {
         &self.student(student).rankings
     }
 
@@ -97,8 +107,8 @@ impl Assignments {
         self.assigned_to[student]
     }
 
-    pub fn project_at_rank(&self, student: StudentId, rank: usize) -> Option<ProjectId> {
-        self.rankings(student).get(rank).copied()
+    pub fn projects_at_rank(&self, student: StudentId, rank: usize) -> &[ProjectId] {
+        self.rankings(student).get(rank).map_or(&[], Vec::as_slice)
     }
 
     pub fn rank_of(&self, student: StudentId, project: ProjectId) -> Option<usize> {
@@ -150,6 +160,49 @@ impl Assignments {
             .is_some_and(|project| self.is_pinned_for(student, project))
     }
 
+    /// Record the ids students and projects had before being remapped,
+    /// in the same order.
+    pub fn set_original_ids(&mut self, students: Vec<StudentId>, projects: Vec<ProjectId>) {
+        assert_eq!(students.len(), self.students.len());
+        assert_eq!(projects.len(), self.projects.len());
+        self.original_student_ids = students;
+        self.original_project_ids = projects;
+    }
+
+    pub fn original_student_id(&self, StudentId(student): StudentId) -> StudentId {
+        self.original_student_ids[student]
+    }
+
+    pub fn original_project_id(&self, ProjectId(project): ProjectId) -> ProjectId {
+        self.original_project_ids[project]
+    }
+
+    /// Record the projects the students were assigned to by a previous
+    /// run, indexed by student.
+    pub fn set_previous_assignments(&mut self, previous: Vec<Option<ProjectId>>) {
+        assert_eq!(previous.len(), self.students.len());
+        let mut counts = vec![0; self.projects.len()];
+        for &ProjectId(p) in previous.iter().flatten() {
+            counts[p] += 1;
+        }
+        self.previous_occurrences = counts
+            .into_iter()
+            .enumerate()
+            .map(|(p, n): (usize, u32)| n.div_ceil(self.max_students(ProjectId(p))))
+            .collect();
+        self.previous = previous;
+    }
+
+    pub fn previous_project_for(&self, StudentId(student): StudentId) -> Option<ProjectId> {
+        self.previous[student]
+    }
+
+    /// Number of occurrences of the project which were needed to host the
+    /// students previously assigned to it.
+    pub fn previous_occurrences(&self, ProjectId(project): ProjectId) -> u32 {
+        self.previous_occurrences[project]
+    }
+
     pub fn is_lazy(&self, StudentId(student): StudentId) -> bool {
         self.students[student].is_lazy()
     }
@@ -1,17 +1,43 @@
 #![allow(clippy::module_name_repetitions)]
 
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub struct ProjectId(pub usize);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Project {
     pub id: ProjectId,
     pub name: String,
     pub min_students: u32,
     pub max_students: u32,
     pub max_occurrences: u32,
+    #[serde(default)]
+    pub info: ProjectInfo,
+}
+
+/// Optional descriptive information about a project, which is not used
+/// by the assignment algorithm.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ProjectInfo {
+    pub url: Option<String>,
+    pub supervisor: Option<String>,
+    pub supervisor_email: Option<String>,
+    pub description: Option<String>,
+}
+
+impl ProjectInfo {
+    /// Supervisor name followed by its email address, if any of them
+    /// is known.
+    pub fn supervisor_contact(&self) -> Option<String> {
+        match (&self.supervisor, &self.supervisor_email) {
+            (Some(name), Some(email)) => Some(format!("{name} <{email}>")),
+            (Some(name), None) => Some(name.clone()),
+            (None, Some(email)) => Some(format!("<{email}>")),
+            (None, None) => None,
+        }
+    }
 }
 
 impl Project {
@@ -50,6 +76,7 @@ fn test_acceptable() {
         min_students: 2,
         max_students: 4,
         max_occurrences: 2,
+        info: ProjectInfo::default(),
     };
     assert_eq!(
         (1..10).filter(|n| p.acceptable(2, *n)).collect::<Vec<_>>(),
@@ -83,6 +110,7 @@ fn test_can_host() {
         min_students: 2,
         max_students: 4,
         max_occurrences: 2,
+        info: ProjectInfo::default(),
     };
     assert_eq!(p.can_host(2), (2..9).collect::<Vec<_>>());
     let p = Project {
@@ -1,18 +1,26 @@
 #![allow(clippy::module_name_repetitions)]
 
 use super::ProjectId;
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt::Display};
 
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub struct StudentId(pub usize);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Student {
     pub id: StudentId,
     pub first_name: String,
     pub last_name: String,
     pub name: String,
-    pub rankings: Vec<ProjectId>,
+    /// Login, which identifies the student across runs.
+    #[serde(default)]
+    pub login: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Ranked projects, best first, grouped into tiers of projects
+    /// which are equally preferred.
+    pub rankings: Vec<Vec<ProjectId>>,
     pub bonuses: HashMap<ProjectId, i64>,
 }
 
@@ -21,7 +29,7 @@ impl Student {
         id: StudentId,
         first_name: String,
         last_name: String,
-        rankings: Vec<ProjectId>,
+        rankings: Vec<Vec<ProjectId>>,
         bonuses: HashMap<ProjectId, i64>,
     ) -> Student {
         let name = format!("{first_name} {last_name}");
@@ -30,13 +38,24 @@ impl Student {
             first_name,
             last_name,
             name,
+            login: None,
+            email: None,
             rankings,
             bonuses,
         }
     }
 
+    /// Return the rank of a project, which is the index of its tier.
+    /// Tied projects have the same rank.
     pub fn rank_of(&self, project: ProjectId) -> Option<usize> {
-        self.rankings.iter().position(|&p| p == project)
+        self.rankings
+            .iter()
+            .position(|tier| tier.contains(&project))
+    }
+
+    /// Return all ranked projects, best first.
+    pub fn ranked_projects(&self) -> impl Iterator<Item = ProjectId> + '_ {
+        self.rankings.iter().flatten().copied()
     }
 
     pub fn is_lazy(&self) -> bool {
@@ -46,6 +65,34 @@ impl Student {
 
 impl Display for Student {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{name}", name = self.name)
+        write!(f, "{name}", name = self.name)?;
+        if let Some(login) = &self.login {
+            write!(f, " ({login})")?;
+        }
+        Ok(())
     }
 }
+
+#[test]
+fn test_rank_of_with_ties() {
+    let student = Student::new(
+        StudentId(0),
+        "Ada".into(),
+        "Lovelace".into(),
+        vec![
+            vec![ProjectId(3)],
+            vec![ProjectId(1), ProjectId(4)],
+            vec![ProjectId(2)],
+        ],
+        HashMap::new(),
+    );
+    assert_eq!(student.rank_of(ProjectId(3)), Some(0));
+    assert_eq!(student.rank_of(ProjectId(1)), Some(1));
+    assert_eq!(student.rank_of(ProjectId(4)), Some(1));
+    assert_eq!(student.rank_of(ProjectId(2)), Some(2));
+    assert_eq!(student.rank_of(ProjectId(5)), None);
+    assert_eq!(
+        student.ranked_projects().collect::<Vec<_>>(),
+        vec![ProjectId(3), ProjectId(1), ProjectId(4), ProjectId(2)]
+    );
+}
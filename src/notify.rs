@@ -0,0 +1,418 @@
+//! Notification of their assignment to students, as messages written into
+//! a maildir or sent through SMTP.
+
+use crate::model::{Assignments, ProjectId, StudentId};
+use eyre::{Context, bail, ensure};
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::warn;
+
+const DEFAULT_SUBJECT: &str = "Project assignment: {project}";
+
+const DEFAULT_BODY: &str = "Hello {first_name},
+
+You have been assigned to the project \"{project}\".
+Supervisor: {supervisor_contact}
+URL: {url}
+";
+
+/// Fields which can be used in templates as `{field}`.
+const FIELDS: [&str; 11] = [
+    "first_name",
+    "last_name",
+    "name",
+    "login",
+    "email",
+    "project",
+    "rank",
+    "supervisor",
+    "supervisor_email",
+    "supervisor_contact",
+    "url",
+];
+
+#[derive(Default, Deserialize)]
+pub struct Config {
+    /// Sender of the messages.
+    pub from: Option<String>,
+    pub reply_to: Option<String>,
+    /// Template of the subject.
+    pub subject: Option<String>,
+    /// File holding the template of the body.
+    pub template: Option<PathBuf>,
+    pub smtp: Option<SmtpConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: Encryption,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encryption {
+    /// Plain connection, for local servers only.
+    None,
+    #[default]
+    Starttls,
+    /// TLS from the start of the connection.
+    Tls,
+}
+
+/// Builder of the messages, with validated headers and templates.
+pub struct Notifier {
+    from: Mailbox,
+    reply_to: Option<Mailbox>,
+    subject: String,
+    body: String,
+}
+
+impl Notifier {
+    pub fn new(config: &Config) -> eyre::Result<Self> {
+        let Some(from) = &config.from else {
+            bail!("no sender configured in the `[notify]` section");
+        };
+        let body = match &config.template {
+            Some(file_name) => std::fs::read_to_string(file_name)
+                .context(format!("cannot read template `{}`", file_name.display()))?,
+            None => String::from(DEFAULT_BODY),
+        };
+        let notifier = Notifier {
+            from: from.parse().context(format!("invalid sender `{from}`"))?,
+            reply_to: config
+                .reply_to
+                .as_ref()
+                .map(|r| r.parse().context(format!("invalid reply-to address `{r}`")))
+                .transpose()?,
+            subject: config
+                .subject
+                .clone()
+                .unwrap_or_else(|| String::from(DEFAULT_SUBJECT)),
+            body,
+        };
+        // Check templates before anything gets computed
+        render(&notifier.subject, |_| String::new()).context("invalid subject template")?;
+        render(&notifier.body, |_| String::new()).context("invalid body template")?;
+        Ok(notifier)
+    }
+
+    /// Build a message for every assigned student. Students without an
+    /// email address, then those with an invalid one, are returned
+    /// separately.
+    pub fn messages(
+        &self,
+        a: &Assignments,
+    ) -> eyre::Result<(Vec<Message>, Vec<StudentId>, Vec<StudentId>)> {
+        let mut messages = Vec::new();
+        let mut missing = Vec::new();
+        let mut invalid = Vec::new();
+        for s in a.all_students() {
+            let Some(p) = a.project_for(s) else {
+                continue;
+            };
+            let Some(email) = &a.student(s).email else {
+                missing.push(s);
+                continue;
+            };
+            let Ok(address) = email.parse() else {
+                invalid.push(s);
+                continue;
+            };
+            let to = Mailbox::new(Some(a.student(s).name.clone()), address);
+            let mut builder = Message::builder()
+                .from(self.from.clone())
+                .to(to)
+                .subject(render(&self.subject, |f| field(a, s, p, f))?)
+                .message_id(None)
+                .header(ContentType::TEXT_PLAIN);
+            if let Some(reply_to) = &self.reply_to {
+                builder = builder.reply_to(reply_to.clone());
+            }
+            messages.push(builder.body(render(&self.body, |f| field(a, s, p, f))?)?);
+        }
+        Ok((messages, missing, invalid))
+    }
+}
+
+/// Value of a template field for student `s` assigned to project `p`.
+fn field(a: &Assignments, s: StudentId, p: ProjectId, name: &str) -> String {
+    let student = a.student(s);
+    let info = &a.project(p).info;
+    let value = match name {
+        "first_name" => Some(student.first_name.clone()),
+        "last_name" => Some(student.last_name.clone()),
+        "name" => Some(student.name.clone()),
+        "login" => student.login.clone(),
+        "email" => student.email.clone(),
+        "project" => Some(a.project(p).name.clone()),
+        "rank" => a.rank_of(s, p).map(|r| (r + 1).to_string()),
+        "supervisor" => info.supervisor.clone(),
+        "supervisor_email" => info.supervisor_email.clone(),
+        "supervisor_contact" => info.supervisor_contact(),
+        "url" => info.url.clone(),
+        _ => unreachable!("unknown field `{name}`"),
+    };
+    value.unwrap_or_default()
+}
+
+/// Replace every `{field}` of `template` by its value. `{{` and `}}` stand
+/// for literal braces.
+fn render(template: &str, value: impl Fn(&str) -> String) -> eyre::Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..i]);
+        let (brace, after) = (&rest[i..=i], &rest[i + 1..]);
+        if after.starts_with(brace) {
+            rendered.push_str(brace);
+            rest = &after[1..];
+        } else if brace == "}" {
+            bail!("unmatched `}}` in template");
+        } else {
+            let Some(end) = after.find('}') else {
+                bail!("unmatched `{{` in template");
+            };
+            let name = &after[..end];
+            ensure!(
+                FIELDS.contains(&name),
+                "unknown field `{name}` in template, known fields are {fields}",
+                fields = FIELDS.join(", ")
+            );
+            rendered.push_str(&value(name));
+            rest = &after[end + 1..];
+        }
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Deliver the messages into the maildir `dir`, which is created if needed.
+pub fn write_maildir(dir: &Path, messages: &[Message]) -> eyre::Result<()> {
+    for sub in ["tmp", "new", "cur"] {
+        let d = dir.join(sub);
+        std::fs::create_dir_all(&d)
+            .context(format!("cannot create directory `{}`", d.display()))?;
+    }
+    let time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+    let pid = std::process::id();
+    for (n, message) in messages.iter().enumerate() {
+        let file_name = format!("{time}.P{pid}Q{n}.rsolver");
+        let tmp = dir.join("tmp").join(&file_name);
+        std::fs::write(&tmp, message.formatted())
+            .context(format!("cannot write `{}`", tmp.display()))?;
+        std::fs::rename(&tmp, dir.join("new").join(&file_name))
+            .context(format!("cannot deliver `{}`", tmp.display()))?;
+    }
+    Ok(())
+}
+
+/// Send the messages through the SMTP server. Every message is attempted
+/// even if some of them fail.
+pub async fn send(config: &SmtpConfig, messages: &[Message]) -> eyre::Result<()> {
+    let mut builder = match config.tls {
+        Encryption::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        Encryption::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+        Encryption::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+    };
+    if let Some(port) = config.port {
+        builder = builder.port(port);
+    }
+    if let Some(user) = &config.user {
+        builder = builder.credentials(Credentials::new(
+            user.clone(),
+            config.password.clone().unwrap_or_default(),
+        ));
+    }
+    let transport = builder.build();
+    let mut failed = 0;
+    for message in messages {
+        if let Err(e) = transport.send(message.clone()).await {
+            let to = message.envelope().to().iter().map(ToString::to_string);
+            warn!(
+                to = %to.collect::<Vec<_>>().join(", "),
+                error = %e,
+                "cannot send message"
+            );
+            failed += 1;
+        }
+    }
+    ensure!(
+        failed == 0,
+        "{failed} out of {n} messages could not be sent",
+        n = messages.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+fn sample_assignments() -> Assignments {
+    use crate::model::{Project, ProjectInfo, Student};
+    use crate::testing;
+    let projects = vec![Project {
+        info: ProjectInfo {
+            supervisor: Some(String::from("Grace Hopper")),
+            url: Some(String::from("https://example.com/robots")),
+            ..ProjectInfo::default()
+        },
+        ..testing::project(0, "Robots", 1, 2)
+    }];
+    let students = vec![
+        Student {
+            email: Some(String::from("ada@example.com")),
+            ..testing::student(0, "Ada", "Lovelace", &[0])
+        },
+        testing::student(1, "Alan", "Turing", &[0]),
+    ];
+    let mut a = Assignments::new(students, projects);
+    a.assign_to(StudentId(0), ProjectId(0));
+    a.assign_to(StudentId(1), ProjectId(0));
+    a
+}
+
+#[test]
+fn test_render() {
+    let value = |f: &str| f.to_uppercase();
+    assert_eq!(
+        render("{{{name}}} got {project}", value).unwrap(),
+        "{NAME} got PROJECT"
+    );
+    assert!(render("{nmae}", value).is_err());
+    assert!(render("{name", value).is_err());
+    assert!(render("name}", value).is_err());
+}
+
+#[test]
+fn test_maildir() {
+    let notifier = Notifier::new(&Config {
+        from: Some(String::from("PAF <paf@example.com>")),
+        subject: Some(String::from("{project} (rank {rank})")),
+        ..Config::default()
+    })
+    .unwrap();
+    let (messages, missing, invalid) = notifier.messages(&sample_assignments()).unwrap();
+    assert_eq!(missing, vec![StudentId(1)]);
+    assert!(invalid.is_empty());
+    let dir = std::env::temp_dir().join(format!("rsolver-maildir-{}", std::process::id()));
+    write_maildir(&dir, &messages).unwrap();
+    let files = std::fs::read_dir(dir.join("new"))
+        .unwrap()
+        .map(|f| std::fs::read_to_string(f.unwrap().path()).unwrap())
+        .collect::<Vec<_>>();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(files.len(), 1);
+    for header in [
+        "From: PAF <paf@example.com>\r\n",
+        "To: \"Ada Lovelace\" <ada@example.com>\r\n",
+        "Subject: Robots (rank 1)\r\n",
+        "Message-ID: <",
+        "Date: ",
+        "Content-Type: text/plain; charset=utf-8\r\n",
+    ] {
+        assert!(files[0].contains(header), "missing {header:?}");
+    }
+    assert!(files[0].contains("Hello Ada,\r\n"));
+    assert!(files[0].contains("Supervisor: Grace Hopper\r\n"));
+}
+
+#[test]
+fn test_invalid_address() {
+    use crate::model::Student;
+    use crate::testing;
+    let projects = vec![testing::project(0, "Robots", 1, 3)];
+    let students = [
+        ("Ada", "Lovelace", "ada@example.com"),
+        ("Alan", "Turing", "alan.example.com"),
+        ("Grace", "Hopper", "grace@example.com"),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(n, (first, last, email))| Student {
+        email: Some(String::from(email)),
+        ..testing::student(n, first, last, &[0])
+    })
+    .collect();
+    let mut a = Assignments::new(students, projects);
+    for s in 0..3 {
+        a.assign_to(StudentId(s), ProjectId(0));
+    }
+    let notifier = Notifier::new(&Config {
+        from: Some(String::from("paf@example.com")),
+        ..Config::default()
+    })
+    .unwrap();
+    let (messages, missing, invalid) = notifier.messages(&a).unwrap();
+    assert_eq!(messages.len(), 2);
+    assert!(missing.is_empty());
+    assert_eq!(invalid, vec![StudentId(1)]);
+}
+
+#[tokio::test]
+async fn test_smtp() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    // Minimal SMTP sink accepting everything, which returns the received
+    // data
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let sink = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let (mut data, mut in_data) = (String::new(), false);
+        writer.write_all(b"220 sink\r\n").await.unwrap();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let reply: &[u8] = if in_data {
+                if line == "." {
+                    in_data = false;
+                    b"250 ok\r\n"
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                    continue;
+                }
+            } else if line == "DATA" {
+                in_data = true;
+                b"354 go on\r\n"
+            } else if line == "QUIT" {
+                writer.write_all(b"221 bye\r\n").await.unwrap();
+                break;
+            } else {
+                b"250 ok\r\n"
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+        data
+    });
+    let notifier = Notifier::new(&Config {
+        from: Some(String::from("paf@example.com")),
+        ..Config::default()
+    })
+    .unwrap();
+    let (messages, _, _) = notifier.messages(&sample_assignments()).unwrap();
+    let config = SmtpConfig {
+        host: String::from("127.0.0.1"),
+        port: Some(port),
+        tls: Encryption::None,
+        user: None,
+        password: None,
+    };
+    send(&config, &messages).await.unwrap();
+    // The connection is closed once the transport has been dropped
+    let data = tokio::time::timeout(std::time::Duration::from_secs(5), sink)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(data.contains("Subject: Project assignment: Robots\n"));
+    assert!(data.contains("URL: https://example.com/robots\n"));
+}
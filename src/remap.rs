@@ -23,7 +23,7 @@ pub fn remap(students: &mut [Student], projects: &mut [Project]) {
     remap_students(students);
     let map = remap_projects(projects);
     for student in students {
-        for id in &mut student.rankings {
+        for id in student.rankings.iter_mut().flatten() {
             *id = map[&*id];
         }
         student.bonuses = student
@@ -34,15 +34,31 @@ pub fn remap(students: &mut [Student], projects: &mut [Project]) {
     }
 }
 
-pub fn separate_lazy(students: Vec<Student>) -> (Vec<Student>, Vec<StudentId>) {
-    let mut non_lazy = Vec::new();
-    let mut lazy = Vec::new();
-    for student in students {
-        if student.rankings.is_empty() {
-            lazy.push(student.id);
-        } else {
-            non_lazy.push(student);
-        }
-    }
-    (non_lazy, lazy)
+/// Convert assignments given with original ids into the project of each
+/// remapped student. Assignments of unknown students or to unknown
+/// projects are ignored.
+pub fn remap_assignments(
+    assignments: &[(StudentId, ProjectId)],
+    students: &[Student],
+    projects: &[Project],
+) -> Vec<Option<ProjectId>> {
+    let projects: HashMap<ProjectId, ProjectId> = projects
+        .iter()
+        .zip(0..)
+        .map(|(p, n)| (p.id, ProjectId(n)))
+        .collect();
+    let assignments: HashMap<StudentId, ProjectId> = assignments.iter().copied().collect();
+    students
+        .iter()
+        .map(|s| {
+            assignments
+                .get(&s.id)
+                .and_then(|p| projects.get(p))
+                .copied()
+        })
+        .collect()
+}
+
+pub fn separate_lazy(students: Vec<Student>) -> (Vec<Student>, Vec<Student>) {
+    students.into_iter().partition(|s| !s.rankings.is_empty())
 }
@@ -0,0 +1,472 @@
+//! Structured content of the reports, shared by the plain-text output
+//! and the JSON document.
+
+use crate::loaders::Cutoff;
+use crate::model::{Assignments, ProjectId, ProjectInfo, Student, StudentId};
+use crate::stats;
+use serde::Serialize;
+use std::fmt::Display;
+
+/// Everything known about the assignments, as a single document.
+#[derive(Serialize)]
+pub struct Report {
+    pub projects: Vec<ProjectReport>,
+    pub stats: Stats,
+    pub missed_bonuses: Vec<MissedBonus>,
+    pub empty_projects: Vec<String>,
+    pub projects_with_many_unregistered: Vec<ManyUnregistered>,
+}
+
+impl Report {
+    pub fn new(
+        a: &Assignments,
+        rename_lazy: bool,
+        eliminated: usize,
+        cutoff: Option<&Cutoff>,
+    ) -> Self {
+        Report {
+            projects: details(a, rename_lazy),
+            stats: stats(a, eliminated, cutoff),
+            missed_bonuses: missed_bonuses(a),
+            empty_projects: empty(a),
+            projects_with_many_unregistered: with_many_lazy(a),
+        }
+    }
+}
+
+/// A student as displayed, with the login if any.
+#[derive(Serialize)]
+pub struct StudentName {
+    pub name: String,
+    pub login: Option<String>,
+}
+
+impl From<&Student> for StudentName {
+    fn from(student: &Student) -> Self {
+        StudentName {
+            name: student.name.clone(),
+            login: student.login.clone(),
+        }
+    }
+}
+
+impl Display for StudentName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{name}", name = self.name)?;
+        if let Some(login) = &self.login {
+            write!(f, " ({login})")?;
+        }
+        Ok(())
+    }
+}
+
+/// An open project with its members.
+#[derive(Serialize)]
+pub struct ProjectReport {
+    pub name: String,
+    pub occurrences: u32,
+    #[serde(flatten)]
+    pub info: ProjectInfo,
+    pub students: Vec<AssignedStudent>,
+}
+
+#[derive(Serialize)]
+pub struct AssignedStudent {
+    #[serde(flatten)]
+    pub student: StudentName,
+    /// Rank of the project for the student, starting at 1.
+    pub rank: Option<usize>,
+    pub pinned: bool,
+}
+
+#[derive(Serialize)]
+pub struct Stats {
+    pub registered: usize,
+    /// Unregistered students, which are not part of the assignments if
+    /// `dropped_unregistered` is set.
+    pub unregistered: usize,
+    pub dropped_unregistered: bool,
+    pub total: usize,
+    pub projects: usize,
+    pub occurrences: u32,
+    /// Number of students at each rank, for ranks given to at least one
+    /// student.
+    pub ranks: Vec<RankCount>,
+    /// Cutoff applied to the preferences and bonuses, with the number of
+    /// late rows which have been ignored.
+    pub cutoff: Option<Cutoff>,
+}
+
+#[derive(Serialize)]
+pub struct RankCount {
+    /// Rank, starting at 1.
+    pub rank: usize,
+    pub students: usize,
+    pub cumulative: usize,
+    pub percent: f32,
+}
+
+/// A student who got a project despite having a bonus for a better ranked
+/// project.
+#[derive(Serialize)]
+pub struct MissedBonus {
+    pub student: StudentName,
+    pub project: String,
+    pub rank: usize,
+    pub bonus_project: String,
+    pub bonus_rank: usize,
+    pub bonus: i64,
+}
+
+/// An open project with at least as many unregistered students as
+/// registered ones.
+#[derive(Serialize)]
+pub struct ManyUnregistered {
+    pub project: String,
+    pub unregistered: usize,
+    pub total: usize,
+}
+
+/// The result of a student designated by login.
+#[derive(Serialize)]
+pub struct StudentResult {
+    pub login: String,
+    /// Name of the student, which is `None` if the student is not part of
+    /// the assignments, having been dropped as unregistered.
+    pub name: Option<String>,
+    pub project: Option<String>,
+    /// Rank of the project for the student, starting at 1.
+    pub rank: Option<usize>,
+}
+
+/// The members of an occurrence of an open project.
+pub struct Roster {
+    pub project: String,
+    /// Occurrence number, starting at 1.
+    pub occurrence: usize,
+    pub occurrences: usize,
+    pub students: Vec<RosterStudent>,
+}
+
+pub struct RosterStudent {
+    pub last_name: String,
+    pub first_name: String,
+    pub login: Option<String>,
+    pub email: Option<String>,
+    /// Rank of the project for the student, starting at 1.
+    pub rank: Option<usize>,
+    pub pinned: bool,
+    pub unregistered: bool,
+}
+
+/// Open projects sorted by name, with their students sorted by name.
+/// Unregistered students are renamed into `Zzz` followed by their order
+/// in the project if `rename_lazy` is set, to ease output comparison.
+pub fn details(a: &Assignments, rename_lazy: bool) -> Vec<ProjectReport> {
+    let mut projects = a.filter_projects(|p| a.is_open(p));
+    projects.sort_by_key(|&p| a.project(p).name.clone());
+    projects
+        .into_iter()
+        .map(|p| {
+            let mut lazy_index = 0;
+            let mut students = a
+                .students_for(p)
+                .iter()
+                .map(|&s| AssignedStudent {
+                    student: if rename_lazy && a.student(s).is_lazy() {
+                        lazy_index += 1;
+                        StudentName {
+                            name: format!("Zzz {lazy_index}"),
+                            login: None,
+                        }
+                    } else {
+                        StudentName::from(a.student(s))
+                    },
+                    rank: a.rank_of(s, p).map(|r| r + 1),
+                    pinned: a.is_pinned_and_has_chosen(s, p),
+                })
+                .collect::<Vec<_>>();
+            students.sort_by_cached_key(|s| s.student.to_string());
+            ProjectReport {
+                name: a.project(p).name.clone(),
+                occurrences: a.max_occurrences(p),
+                info: a.project(p).info.clone(),
+                students,
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn stats(a: &Assignments, eliminated: usize, cutoff: Option<&Cutoff>) -> Stats {
+    let students = a.all_students().len();
+    let lazy = a.filter_students(|s| a.is_lazy(s)).len();
+    assert!(
+        lazy == 0 || eliminated == 0,
+        "cannot have lazy students if they have been eliminated"
+    );
+    let ranks = stats::statistics(a);
+    let total: usize = ranks.iter().sum();
+    let mut cumulative = 0;
+    let ranks = ranks
+        .into_iter()
+        .enumerate()
+        .filter_map(|(rank, n)| {
+            cumulative += n;
+            (n != 0).then_some(RankCount {
+                rank: rank + 1,
+                students: n,
+                cumulative,
+                percent: 100.0 * cumulative as f32 / total as f32,
+            })
+        })
+        .collect();
+    let open = a.filter_projects(|p| a.is_open(p));
+    Stats {
+        registered: students - lazy,
+        unregistered: lazy.max(eliminated),
+        dropped_unregistered: eliminated > 0,
+        total: students + eliminated,
+        projects: open.len(),
+        occurrences: open.iter().map(|&p| a.max_occurrences(p)).sum(),
+        ranks,
+        cutoff: cutoff.cloned(),
+    }
+}
+
+pub fn missed_bonuses(a: &Assignments) -> Vec<MissedBonus> {
+    let mut missed_bonuses = a
+        .all_students()
+        .into_iter()
+        .flat_map(|s| {
+            if let Some(p) = a.project_for(s) {
+                if a.bonus(s, p).is_some() {
+                    vec![]
+                } else if let Some(r) = a.rank_of(s, p) {
+                    let mut bonuses = a
+                        .bonuses(s)
+                        .iter()
+                        .filter_map(|(&pp, &b)| {
+                            a.rank_of(s, pp).and_then(|rr| {
+                                if rr < r && b > 0 {
+                                    Some((s, p, r, pp, rr, b))
+                                } else {
+                                    None
+                                }
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    bonuses.sort_by_key(|&(_s, _p, _r, _pp, rr, _b)| rr);
+                    bonuses
+                } else {
+                    vec![]
+                }
+            } else {
+                vec![]
+            }
+        })
+        .collect::<Vec<_>>();
+    missed_bonuses.sort_by_key(|&(s, _p, _r, pp, _rr, b)| {
+        (a.project(pp).name.clone(), -b, a.student(s).name.clone())
+    });
+    missed_bonuses
+        .into_iter()
+        .map(|(s, p, r, pp, rr, b)| MissedBonus {
+            student: StudentName::from(a.student(s)),
+            project: a.project(p).name.clone(),
+            rank: r + 1,
+            bonus_project: a.project(pp).name.clone(),
+            bonus_rank: rr + 1,
+            bonus: b,
+        })
+        .collect()
+}
+
+/// Names of the projects which have not been opened, sorted.
+pub fn empty(a: &Assignments) -> Vec<String> {
+    let mut projects = a
+        .filter_projects(|p| !a.is_open(p))
+        .into_iter()
+        .map(|p| a.project(p).name.clone())
+        .collect::<Vec<_>>();
+    projects.sort();
+    projects
+}
+
+pub fn with_many_lazy(a: &Assignments) -> Vec<ManyUnregistered> {
+    let mut projects = a
+        .filter_projects(|p| a.is_open(p))
+        .iter()
+        .filter_map(|&p| {
+            let lazy = a.students_for(p).iter().filter(|&&s| a.is_lazy(s)).count();
+            let regular = a.students_for(p).len() - lazy;
+            (lazy >= regular).then(|| ManyUnregistered {
+                project: a.project(p).name.clone(),
+                unregistered: lazy,
+                total: lazy + regular,
+            })
+        })
+        .collect::<Vec<_>>();
+    projects.sort_by(|p1, p2| p1.project.cmp(&p2.project));
+    projects
+}
+
+/// Split the students of a project into as few occurrences as possible,
+/// with sizes differing by at most one. The solver only decides which
+/// project students get, so they are split by alphabetical order.
+pub fn occurrences(a: &Assignments, p: ProjectId) -> Vec<Vec<StudentId>> {
+    let mut students = a.students_for(p).clone();
+    students.sort_by_cached_key(|&s| {
+        let student = a.student(s);
+        (student.last_name.clone(), student.first_name.clone())
+    });
+    let n = students.len();
+    let groups = a.current_occurrences(p) as usize;
+    let mut rest = students.as_slice();
+    (0..groups)
+        .map(|i| {
+            let (group, next) = rest.split_at(n / groups + usize::from(i < n % groups));
+            rest = next;
+            group.to_vec()
+        })
+        .collect()
+}
+
+/// Results of the students with the given logins, in the same order.
+pub fn student_results(a: &Assignments, logins: &[String]) -> Vec<StudentResult> {
+    logins
+        .iter()
+        .map(|login| {
+            let s = a
+                .all_students()
+                .into_iter()
+                .find(|&s| a.student(s).login.as_ref() == Some(login));
+            let project = s.and_then(|s| a.project_for(s));
+            StudentResult {
+                login: login.clone(),
+                name: s.map(|s| a.student(s).name.clone()),
+                project: project.map(|p| a.project(p).name.clone()),
+                rank: s
+                    .zip(project)
+                    .and_then(|(s, p)| a.rank_of(s, p))
+                    .map(|r| r + 1),
+            }
+        })
+        .collect()
+}
+
+/// Rosters of every occurrence of the open projects, sorted by project
+/// name.
+pub fn rosters(a: &Assignments) -> Vec<Roster> {
+    let mut projects = a.filter_projects(|p| a.is_open(p));
+    projects.sort_by_key(|&p| a.project(p).name.clone());
+    projects
+        .into_iter()
+        .flat_map(|p| {
+            let occurrences = occurrences(a, p);
+            let count = occurrences.len();
+            occurrences
+                .into_iter()
+                .enumerate()
+                .map(move |(i, students)| Roster {
+                    project: a.project(p).name.clone(),
+                    occurrence: i + 1,
+                    occurrences: count,
+                    students: students
+                        .into_iter()
+                        .map(|s| {
+                            let student = a.student(s);
+                            RosterStudent {
+                                last_name: student.last_name.clone(),
+                                first_name: student.first_name.clone(),
+                                login: student.login.clone(),
+                                email: student.email.clone(),
+                                rank: a.rank_of(s, p).map(|r| r + 1),
+                                pinned: a.is_pinned_and_has_chosen(s, p),
+                                unregistered: a.is_lazy(s),
+                            }
+                        })
+                        .collect(),
+                })
+        })
+        .collect()
+}
+
+#[test]
+fn test_occurrences() {
+    use crate::model::Project;
+    use crate::testing;
+    let project = Project {
+        max_occurrences: 3,
+        ..testing::project(0, "Project", 3, 4)
+    };
+    let students = (0..7)
+        .map(|n| testing::student(n, "Student", &format!("{}", 6 - n), &[0]))
+        .collect();
+    let mut a = Assignments::new(students, vec![project]);
+    for s in a.all_students() {
+        a.assign_to(s, ProjectId(0));
+    }
+    assert_eq!(
+        occurrences(&a, ProjectId(0)),
+        vec![
+            vec![StudentId(6), StudentId(5), StudentId(4), StudentId(3)],
+            vec![StudentId(2), StudentId(1), StudentId(0)],
+        ]
+    );
+    let rosters = rosters(&a);
+    assert_eq!(rosters.len(), 2);
+    assert_eq!(rosters[1].occurrence, 2);
+    assert_eq!(rosters[1].occurrences, 2);
+    assert_eq!(rosters[1].students[0].last_name, "4");
+    assert_eq!(rosters[1].students[0].rank, Some(1));
+}
+
+#[test]
+fn test_report() {
+    use crate::model::{ProjectId, StudentId};
+    use crate::testing;
+    use std::collections::HashMap;
+    let projects = (0..3)
+        .map(|n| testing::project(n, &format!("Project {n}"), 1, 2))
+        .collect();
+    let students = vec![
+        Student {
+            bonuses: HashMap::from([(ProjectId(0), 10)]),
+            ..testing::student(0, "Ada", "Lovelace", &[0, 1])
+        },
+        Student {
+            login: Some("aturing".into()),
+            ..testing::student(1, "Alan", "Turing", &[0])
+        },
+        testing::student(2, "Lazy", "Student", &[]),
+    ];
+    let mut a = Assignments::new(students, projects);
+    a.assign_to(StudentId(0), ProjectId(1));
+    a.assign_to(StudentId(1), ProjectId(0));
+    a.assign_to(StudentId(2), ProjectId(1));
+    let report = serde_json::to_value(Report::new(&a, true, 0, None)).unwrap();
+    assert_eq!(
+        report["projects"][0]["students"],
+        serde_json::json!([{"name": "Alan Turing", "login": "aturing", "rank": 1, "pinned": false}])
+    );
+    assert_eq!(report["projects"][1]["students"][1]["name"], "Zzz 1");
+    assert_eq!(report["stats"]["registered"], 2);
+    let results =
+        serde_json::to_value(student_results(&a, &["aturing".into(), "nobody".into()])).unwrap();
+    assert_eq!(
+        results,
+        serde_json::json!([
+            {"login": "aturing", "name": "Alan Turing", "project": "Project 0", "rank": 1},
+            {"login": "nobody", "name": null, "project": null, "rank": null},
+        ])
+    );
+    assert_eq!(report["stats"]["unregistered"], 1);
+    assert_eq!(report["stats"]["ranks"][1]["cumulative"], 2);
+    assert_eq!(report["missed_bonuses"][0]["bonus_project"], "Project 0");
+    assert_eq!(report["empty_projects"], serde_json::json!(["Project 2"]));
+    assert_eq!(
+        report["projects_with_many_unregistered"],
+        serde_json::json!([{"project": "Project 1", "unregistered": 1, "total": 2}])
+    );
+}
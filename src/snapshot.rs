@@ -0,0 +1,79 @@
+use crate::hungarian;
+use crate::model::{Project, Student};
+use eyre::{Context, ensure};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Version of the snapshot format. It must be increased every time the
+/// format changes in an incompatible way.
+const VERSION: u32 = 2;
+
+/// A self-contained problem instance, with the configuration in effect
+/// when it was exported.
+#[derive(Deserialize, Serialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub hungarian: hungarian::Config,
+    pub students: Vec<Student>,
+    pub projects: Vec<Project>,
+}
+
+impl Snapshot {
+    pub fn new(
+        students: Vec<Student>,
+        projects: Vec<Project>,
+        hungarian: hungarian::Config,
+    ) -> Self {
+        Self {
+            version: VERSION,
+            hungarian,
+            students,
+            projects,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(file_name: P) -> eyre::Result<Snapshot> {
+        let file_name = file_name.as_ref();
+        let snapshot: Snapshot = serde_json::from_str(
+            &std::fs::read_to_string(file_name)
+                .context(format!("cannot read snapshot `{}`", file_name.display()))?,
+        )
+        .context(format!("invalid snapshot `{}`", file_name.display()))?;
+        ensure!(
+            snapshot.version == VERSION,
+            "snapshot `{}` has version {}, only version {VERSION} is supported",
+            file_name.display(),
+            snapshot.version
+        );
+        Ok(snapshot)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, file_name: P) -> eyre::Result<()> {
+        let file_name = file_name.as_ref();
+        std::fs::write(file_name, serde_json::to_string_pretty(self)?)
+            .context(format!("cannot write snapshot `{}`", file_name.display()))
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    use crate::model::ProjectId;
+    use crate::testing;
+    use std::collections::HashMap;
+    let project = testing::project(4, "Robots", 2, 4);
+    let student = Student {
+        bonuses: HashMap::from([(ProjectId(4), 1000)]),
+        ..testing::student(8, "Ada", "Lovelace", &[4])
+    };
+    let file_name =
+        std::env::temp_dir().join(format!("rsolver-snapshot-{}.json", std::process::id()));
+    Snapshot::new(vec![student], vec![project], hungarian::Config::default())
+        .save(&file_name)
+        .unwrap();
+    let snapshot = Snapshot::load(&file_name).unwrap();
+    std::fs::remove_file(&file_name).unwrap();
+    assert_eq!(snapshot.projects[0].name, "Robots");
+    assert_eq!(snapshot.students[0].name, "Ada Lovelace");
+    assert_eq!(snapshot.students[0].rankings, vec![vec![ProjectId(4)]]);
+    assert_eq!(snapshot.students[0].bonuses[&ProjectId(4)], 1000);
+}
@@ -0,0 +1,425 @@
+use crate::hungarian;
+use crate::loaders::{Backend, Run, RunAssignment};
+use crate::model::{Assignments, Project, ProjectId, Student, StudentId};
+use crate::remap;
+use eyre::ensure;
+use std::collections::HashMap;
+
+/// A student whose new assignment differs from the stored one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    pub student: StudentId,
+    pub old: Option<ProjectId>,
+    pub new: Option<ProjectId>,
+}
+
+/// Compare the stored assignments with the ones of a run.
+pub fn changes(stored: &[(StudentId, ProjectId)], run: &Run) -> Vec<Change> {
+    let stored = stored.iter().copied().collect::<HashMap<_, _>>();
+    run.assignments
+        .iter()
+        .filter_map(|a| {
+            let old = stored.get(&a.student).copied();
+            (old != a.project).then_some(Change {
+                student: a.student,
+                old,
+                new: a.project,
+            })
+        })
+        .collect()
+}
+
+/// Refuse to commit if more than `max_changes` students would change.
+/// `changes` is `None` when no assignments are stored yet.
+pub fn check_changes(changes: Option<&[Change]>, max_changes: Option<usize>) -> eyre::Result<()> {
+    if let Some(max_changes) = max_changes {
+        let n = changes.map_or(0, <[Change]>::len);
+        ensure!(
+            n <= max_changes,
+            "refusing to commit as {n} students would change, more than the {max_changes} allowed"
+        );
+    }
+    Ok(())
+}
+
+/// The result of [`solve`].
+pub struct Solution {
+    pub assignments: Assignments,
+    /// Lazy students dropped from the system, with their original ids.
+    pub lazy_students: Vec<Student>,
+    /// Changes from the stored assignments, if any are stored.
+    pub changes: Option<Vec<Change>>,
+}
+
+/// Compute the assignments for students and projects loaded from the
+/// backend, and store them back into the backend if `commit` is set.
+/// Changes from the assignments already stored in the backend, if any,
+/// are returned, and committing is refused if more than `max_changes`
+/// students would change. Nothing counts as a change when no
+/// assignment is stored yet. Lazy students which have been dropped from
+/// the system when `drop_unregistered` is set are returned alongside
+/// the assignments, with their original ids.
+pub async fn solve<B: Backend>(
+    backend: &mut B,
+    original_students: Vec<Student>,
+    original_projects: Vec<Project>,
+    config: &hungarian::Config,
+    drop_unregistered: bool,
+    commit: bool,
+    max_changes: Option<usize>,
+) -> eyre::Result<Solution> {
+    // Isolate lazy students before remapping if asked to do so
+    let (original_students, lazy_students) = if drop_unregistered {
+        remap::separate_lazy(original_students)
+    } else {
+        (original_students, vec![])
+    };
+    // Remap students and projects into contiguous values for the algorithm sake
+    let (students, projects) = {
+        let (mut students, mut projects) = (original_students.clone(), original_projects.clone());
+        // Work with normalized values (students and projets starting at 0 and without gaps)
+        remap::remap(&mut students, &mut projects);
+        (students, projects)
+    };
+    // Compute the new assignments, trying to keep the stored ones if
+    // asked to do so
+    let stored = backend.load_assignments().await?;
+    let mut assignments = Assignments::new(students, projects);
+    assignments.set_original_ids(
+        original_students.iter().map(|s| s.id).collect(),
+        original_projects.iter().map(|p| p.id).collect(),
+    );
+    if config.stability_penalty() > 0 {
+        assignments.set_previous_assignments(remap::remap_assignments(
+            &stored,
+            &original_students,
+            &original_projects,
+        ));
+    }
+    hungarian::assign(&mut assignments, config)?;
+    // Every student gets a record, including lazy students which were
+    // singled out beforehand and are not assigned
+    let mut records = assignments
+        .all_students()
+        .into_iter()
+        .map(|s| {
+            let project = assignments.project_for(s);
+            RunAssignment {
+                student: original_students[s.0].id,
+                project: project.map(|p| original_projects[p.0].id),
+                rank: project.and_then(|p| assignments.rank_of(s, p)),
+            }
+        })
+        .chain(lazy_students.iter().map(|student| RunAssignment {
+            student: student.id,
+            project: None,
+            rank: None,
+        }))
+        .collect::<Vec<_>>();
+    records.sort_by_key(|r| r.student);
+    let run = Run {
+        hungarian: config.resolved(),
+        drop_unregistered,
+        cutoff: backend.cutoff(),
+        assignments: records,
+    };
+    // Compare with the stored assignments, unless there are none, in which
+    // case this is the first run and nothing is considered a change
+    let changes = (!stored.is_empty()).then(|| changes(&stored, &run));
+    // Save the results if requested
+    if commit {
+        check_changes(changes.as_deref(), max_changes)?;
+        backend.save_run(&run).await?;
+    }
+    Ok(Solution {
+        assignments,
+        lazy_students,
+        changes,
+    })
+}
+
+#[cfg(test)]
+use crate::loaders::MemoryBackend;
+
+#[cfg(test)]
+fn sample_backend() -> MemoryBackend {
+    use crate::testing;
+    let projects = (0..3)
+        .map(|n| testing::project(10 + n, &format!("Project {n}"), 2, 3))
+        .collect();
+    let students = (0..6)
+        .map(|n| {
+            let rankings = if n == 5 {
+                vec![]
+            } else {
+                vec![10 + n % 3, 10 + (n + 1) % 3]
+            };
+            testing::student(
+                100 + n,
+                &format!("First {n}"),
+                &format!("Last {n}"),
+                &rankings,
+            )
+        })
+        .collect();
+    MemoryBackend::new(students, projects)
+}
+
+#[tokio::test]
+async fn test_solve_in_memory() {
+    let mut backend = sample_backend();
+    let (students, projects) = backend.load().await.unwrap();
+    let Solution {
+        assignments,
+        lazy_students,
+        changes,
+    } = solve(
+        &mut backend,
+        students,
+        projects,
+        &hungarian::Config::default(),
+        false,
+        true,
+        None,
+    )
+    .await
+    .unwrap();
+    assert!(lazy_students.is_empty());
+    assert!(changes.is_none());
+    assert!(assignments.unassigned_students().is_empty());
+    assert_eq!(backend.assignments.len(), 6);
+    assert!(backend.unassigned.is_empty());
+    for &(s, p) in &backend.assignments {
+        assert!((100..106).contains(&s.0));
+        assert!((10..13).contains(&p.0));
+    }
+}
+
+#[tokio::test]
+async fn test_solve_dry_run_and_drop_unregistered() {
+    let mut backend = sample_backend();
+    let (students, projects) = backend.load().await.unwrap();
+    let Solution {
+        assignments,
+        lazy_students,
+        ..
+    } = solve(
+        &mut backend,
+        students,
+        projects,
+        &hungarian::Config::default(),
+        true,
+        false,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        lazy_students.iter().map(|s| s.id).collect::<Vec<_>>(),
+        vec![StudentId(105)]
+    );
+    assert_eq!(assignments.all_students().len(), 5);
+    assert!(backend.assignments.is_empty());
+}
+
+#[tokio::test]
+async fn test_solve_max_changes() {
+    let mut backend = sample_backend();
+    let (students, projects) = backend.load().await.unwrap();
+    let config = hungarian::Config::default();
+    solve(
+        &mut backend,
+        students.clone(),
+        projects.clone(),
+        &config,
+        false,
+        true,
+        Some(0),
+    )
+    .await
+    .unwrap();
+    let committed = backend.assignments.clone();
+    // Move two students around so that the next run changes them back
+    let other = committed
+        .iter()
+        .position(|&(_, p)| p != committed[0].1)
+        .unwrap();
+    backend.assignments.swap(0, other);
+    backend.assignments[0].0 = committed[0].0;
+    backend.assignments[other].0 = committed[other].0;
+    let Err(err) = solve(
+        &mut backend,
+        students.clone(),
+        projects.clone(),
+        &config,
+        false,
+        true,
+        Some(1),
+    )
+    .await
+    else {
+        panic!("committing should have been refused");
+    };
+    assert!(err.to_string().contains("2 students would change"));
+    assert_ne!(backend.assignments, committed);
+    let solution = solve(
+        &mut backend,
+        students,
+        projects,
+        &config,
+        false,
+        true,
+        Some(2),
+    )
+    .await
+    .unwrap();
+    assert_eq!(solution.changes.map(|c| c.len()), Some(2));
+    assert_eq!(backend.assignments, committed);
+}
+
+#[test]
+fn test_changes() {
+    use crate::model::ProjectId;
+    let record = |student, project: Option<usize>| RunAssignment {
+        student: StudentId(student),
+        project: project.map(ProjectId),
+        rank: None,
+    };
+    let run = Run {
+        hungarian: hungarian::Config::default(),
+        drop_unregistered: false,
+        cutoff: None,
+        assignments: vec![record(1, Some(10)), record(2, Some(11)), record(3, None)],
+    };
+    let stored = [
+        (StudentId(1), ProjectId(10)),
+        (StudentId(2), ProjectId(10)),
+        (StudentId(3), ProjectId(11)),
+    ];
+    assert_eq!(
+        changes(&stored, &run),
+        vec![
+            Change {
+                student: StudentId(2),
+                old: Some(ProjectId(10)),
+                new: Some(ProjectId(11)),
+            },
+            Change {
+                student: StudentId(3),
+                old: Some(ProjectId(11)),
+                new: None,
+            },
+        ]
+    );
+    assert!(
+        changes(&[(StudentId(1), ProjectId(10))], &run)[0]
+            .old
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_solve_with_stability_penalty() {
+    use crate::model::ProjectId;
+    use crate::testing;
+    use std::collections::HashMap;
+    let projects = (0..2)
+        .map(|n| testing::project(10 + n, &format!("Project {n}"), 1, 1))
+        .collect::<Vec<_>>();
+    // The first student got a bonus on the first project after the
+    // assignments were stored
+    let students = (0..2)
+        .map(|n| Student {
+            bonuses: if n == 0 {
+                HashMap::from([(ProjectId(10), 100)])
+            } else {
+                HashMap::new()
+            },
+            ..testing::student(
+                100 + n,
+                &format!("First {n}"),
+                &format!("Last {n}"),
+                &[10, 11],
+            )
+        })
+        .collect::<Vec<_>>();
+    let stored = vec![
+        (StudentId(100), ProjectId(11)),
+        (StudentId(101), ProjectId(10)),
+    ];
+    for (penalty, expected) in [(None, [10, 11]), (Some(1000), [11, 10])] {
+        let mut backend = MemoryBackend::new(students.clone(), projects.clone());
+        backend.assignments = stored.clone();
+        let config: hungarian::Config = match penalty {
+            Some(penalty) => toml::from_str(&format!("stability_penalty = {penalty}")).unwrap(),
+            None => hungarian::Config::default(),
+        };
+        solve(
+            &mut backend,
+            students.clone(),
+            projects.clone(),
+            &config,
+            false,
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            backend.assignments,
+            vec![
+                (StudentId(100), ProjectId(expected[0])),
+                (StudentId(101), ProjectId(expected[1])),
+            ]
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_solve_keeps_previous_occurrences() {
+    use crate::model::ProjectId;
+    use crate::testing;
+    let projects = (0..2)
+        .map(|n| testing::project(10 + n, &format!("Project {n}"), 2, 2))
+        .collect::<Vec<_>>();
+    // The first student is kept in the second project by the stability
+    // penalty, while the new student gets the first one, so that both
+    // projects lack a student. The second project weighs more, but was
+    // needed by the stored assignments.
+    let students = (0..2)
+        .map(|n| {
+            testing::student(
+                100 + n,
+                &format!("First {n}"),
+                &format!("Last {n}"),
+                &[10, 11],
+            )
+        })
+        .collect::<Vec<_>>();
+    for (penalty, expected) in [(None, 10), (Some(1000), 11)] {
+        let mut backend = MemoryBackend::new(students.clone(), projects.clone());
+        backend.assignments = vec![(StudentId(100), ProjectId(11))];
+        let config: hungarian::Config = match penalty {
+            Some(penalty) => toml::from_str(&format!("stability_penalty = {penalty}")).unwrap(),
+            None => hungarian::Config::default(),
+        };
+        solve(
+            &mut backend,
+            students.clone(),
+            projects.clone(),
+            &config,
+            false,
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            backend.assignments,
+            vec![
+                (StudentId(100), ProjectId(expected)),
+                (StudentId(101), ProjectId(expected)),
+            ]
+        );
+    }
+}
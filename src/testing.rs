@@ -0,0 +1,29 @@
+//! Projects and students shared by the tests.
+
+use crate::model::{Project, ProjectId, ProjectInfo, Student, StudentId};
+use std::collections::HashMap;
+
+/// Project `id` named `name`, with a single occurrence of `min_students`
+/// to `max_students` students and no information.
+pub fn project(id: usize, name: &str, min_students: u32, max_students: u32) -> Project {
+    Project {
+        id: ProjectId(id),
+        name: name.into(),
+        min_students,
+        max_students,
+        max_occurrences: 1,
+        info: ProjectInfo::default(),
+    }
+}
+
+/// Student `id` who ranked `rankings`, one project per tier, and has no
+/// bonus.
+pub fn student(id: usize, first_name: &str, last_name: &str, rankings: &[usize]) -> Student {
+    Student::new(
+        StudentId(id),
+        first_name.into(),
+        last_name.into(),
+        rankings.iter().map(|&p| vec![ProjectId(p)]).collect(),
+        HashMap::new(),
+    )
+}
@@ -0,0 +1,118 @@
+use eyre::{bail, ensure};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// A date and time, without time zone, stored in its normalized
+/// `YYYY-MM-DD HH:MM:SS` form so that timestamps can be compared
+/// as strings, including by databases.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Timestamp(String);
+
+impl Timestamp {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = eyre::Report;
+
+    /// Parse `YYYY-MM-DD`, `YYYY-MM-DD HH:MM` or `YYYY-MM-DD HH:MM:SS`,
+    /// where the space may be replaced by `T`. A date without a time
+    /// designates the last second of this day.
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        let s = s.trim();
+        let (date, time) = match s.split_once([' ', 'T']) {
+            Some((date, time)) => (date, time),
+            None => (s, "23:59:59"),
+        };
+        let fields = |part: &str, sep: char| {
+            part.split(sep)
+                .map(|n| {
+                    ensure!(
+                        !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()),
+                        "invalid number `{n}`"
+                    );
+                    Ok(n.parse::<u32>()?)
+                })
+                .collect::<eyre::Result<Vec<_>>>()
+        };
+        let (Ok(date), Ok(time)) = (fields(date, '-'), fields(time, ':')) else {
+            bail!("invalid timestamp `{s}`, expected `YYYY-MM-DD HH:MM:SS`");
+        };
+        let (year, month, day, hours, minutes, seconds) = match (&date[..], &time[..]) {
+            (&[y, mo, d], &[h, mi]) => (y, mo, d, h, mi, 0),
+            (&[y, mo, d], &[h, mi, s]) => (y, mo, d, h, mi, s),
+            _ => bail!("invalid timestamp `{s}`, expected `YYYY-MM-DD HH:MM:SS`"),
+        };
+        ensure!(
+            (1000..=9999).contains(&year)
+                && (1..=12).contains(&month)
+                && (1..=days_in_month(year, month)).contains(&day)
+                && hours < 24
+                && minutes < 60
+                && seconds < 60,
+            "invalid timestamp `{s}`"
+        );
+        Ok(Timestamp(format!(
+            "{year:04}-{month:02}-{day:02} {hours:02}:{minutes:02}:{seconds:02}"
+        )))
+    }
+}
+
+impl TryFrom<String> for Timestamp {
+    type Error = eyre::Report;
+
+    fn try_from(s: String) -> eyre::Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Timestamp> for String {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+/// Number of days of `month` (starting at 1) in the Gregorian calendar.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[test]
+fn test_parse() {
+    let parse = |s: &str| s.parse::<Timestamp>().map(|t| t.to_string()).ok();
+    assert_eq!(parse("2018-06-10"), Some("2018-06-10 23:59:59".into()));
+    assert_eq!(parse("2018-6-1 8:05"), Some("2018-06-01 08:05:00".into()));
+    assert_eq!(
+        parse("2018-06-10T12:30:15"),
+        Some("2018-06-10 12:30:15".into())
+    );
+    assert_eq!(parse("2018-13-10"), None);
+    assert_eq!(parse("2018-06-10 24:00"), None);
+    assert_eq!(parse("2018-06"), None);
+    assert_eq!(parse("2018-02-31"), None);
+    assert_eq!(parse("2018-04-31"), None);
+    assert_eq!(parse("2018-02-29"), None);
+    assert_eq!(parse("2000-02-29"), Some("2000-02-29 23:59:59".into()));
+    assert_eq!(parse("2020-02-29"), Some("2020-02-29 23:59:59".into()));
+    assert_eq!(parse("1900-02-29"), None);
+    assert_eq!(parse("0-01-01"), None);
+    assert_eq!(parse("20180-01-01"), None);
+    assert_eq!(parse("tomorrow"), None);
+}
@@ -0,0 +1,289 @@
+//! Referential-integrity and data-quality checks run on the loaded data
+//! before solving. Every problem is collected so that they can all be
+//! fixed at once.
+
+use crate::model::{Project, ProjectId, Student, StudentId};
+use crate::timestamp::Timestamp;
+use eyre::bail;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use tracing::{error, warn};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// Several projects share the same id.
+    DuplicateProject(ProjectId),
+    /// Several students share the same id.
+    DuplicateStudent(StudentId),
+    /// Several students share the same login, which cannot be used to
+    /// identify them.
+    DuplicateLogin(String),
+    /// A project requires more students than it can host.
+    InvalidQuota {
+        project: ProjectId,
+        min: u32,
+        max: u32,
+    },
+    /// A project has no occurrence and can never be opened.
+    NoOccurrences(ProjectId),
+    /// A preference references a project which does not exist.
+    UnknownRankedProject {
+        student: StudentId,
+        project: ProjectId,
+    },
+    /// A bonus references a project which does not exist.
+    UnknownBonusProject {
+        student: StudentId,
+        project: ProjectId,
+    },
+    /// A preference references a student which does not exist.
+    UnknownPreferenceStudent {
+        student: StudentId,
+        project: ProjectId,
+    },
+    /// A bonus references a student which does not exist.
+    UnknownBonusStudent {
+        student: StudentId,
+        project: ProjectId,
+    },
+    /// A student ranked the same project several times.
+    DuplicateRanking {
+        student: StudentId,
+        project: ProjectId,
+    },
+    /// Preferences dated after the cutoff have been ignored.
+    LatePreferences { count: usize, as_of: Timestamp },
+    /// Bonuses dated after the cutoff have been ignored.
+    LateBonuses { count: usize, as_of: Timestamp },
+}
+
+impl Problem {
+    /// Errors prevent the assignment from being computed, while other
+    /// problems are only reported.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Problem::DuplicateProject(_)
+                | Problem::DuplicateStudent(_)
+                | Problem::InvalidQuota { .. }
+                | Problem::UnknownRankedProject { .. }
+                | Problem::UnknownBonusProject { .. }
+        )
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::DuplicateProject(p) => {
+                write!(f, "project {p} is defined several times", p = p.0)
+            }
+            Problem::DuplicateStudent(s) => {
+                write!(f, "student {s} is defined several times", s = s.0)
+            }
+            Problem::DuplicateLogin(login) => {
+                write!(f, "login `{login}` is used by several students")
+            }
+            Problem::InvalidQuota { project, min, max } => write!(
+                f,
+                "project {p} requires at least {min} students but accepts at most {max}",
+                p = project.0
+            ),
+            Problem::NoOccurrences(p) => write!(f, "project {p} has no occurrence", p = p.0),
+            Problem::UnknownRankedProject { student, project } => write!(
+                f,
+                "student {s} ranked unknown project {p}",
+                s = student.0,
+                p = project.0
+            ),
+            Problem::UnknownBonusProject { student, project } => write!(
+                f,
+                "student {s} has a bonus for unknown project {p}",
+                s = student.0,
+                p = project.0
+            ),
+            Problem::UnknownPreferenceStudent { student, project } => write!(
+                f,
+                "unknown student {s} ranked project {p}",
+                s = student.0,
+                p = project.0
+            ),
+            Problem::UnknownBonusStudent { student, project } => write!(
+                f,
+                "unknown student {s} has a bonus for project {p}",
+                s = student.0,
+                p = project.0
+            ),
+            Problem::DuplicateRanking { student, project } => write!(
+                f,
+                "student {s} ranked project {p} several times",
+                s = student.0,
+                p = project.0
+            ),
+            Problem::LatePreferences { count, as_of } => {
+                write!(
+                    f,
+                    "{count} preferences dated after {as_of} have been ignored"
+                )
+            }
+            Problem::LateBonuses { count, as_of } => {
+                write!(f, "{count} bonuses dated after {as_of} have been ignored")
+            }
+        }
+    }
+}
+
+/// Check the loaded students and projects. `problems` contains the
+/// problems already found by the backend in the raw data, and is
+/// completed with the problems found in the model.
+pub fn validate(
+    students: &[Student],
+    projects: &[Project],
+    mut problems: Vec<Problem>,
+) -> Vec<Problem> {
+    let mut project_ids = HashSet::new();
+    for project in projects {
+        if !project_ids.insert(project.id) {
+            problems.push(Problem::DuplicateProject(project.id));
+        }
+        if project.min_students > project.max_students {
+            problems.push(Problem::InvalidQuota {
+                project: project.id,
+                min: project.min_students,
+                max: project.max_students,
+            });
+        }
+        if project.max_occurrences == 0 {
+            problems.push(Problem::NoOccurrences(project.id));
+        }
+    }
+    let mut student_ids = HashSet::new();
+    let mut logins = HashSet::new();
+    for student in students {
+        if !student_ids.insert(student.id) {
+            problems.push(Problem::DuplicateStudent(student.id));
+        }
+        if let Some(login) = &student.login
+            && !logins.insert(login)
+        {
+            problems.push(Problem::DuplicateLogin(login.clone()));
+        }
+        let mut ranked = HashMap::new();
+        for project in student.ranked_projects() {
+            if !project_ids.contains(&project) {
+                problems.push(Problem::UnknownRankedProject {
+                    student: student.id,
+                    project,
+                });
+            }
+            let count = ranked.entry(project).or_insert(0);
+            *count += 1;
+            if *count == 2 {
+                problems.push(Problem::DuplicateRanking {
+                    student: student.id,
+                    project,
+                });
+            }
+        }
+        let mut bonuses = student.bonuses.keys().copied().collect::<Vec<_>>();
+        bonuses.sort_by_key(|p| p.0);
+        for project in bonuses {
+            if !project_ids.contains(&project) {
+                problems.push(Problem::UnknownBonusProject {
+                    student: student.id,
+                    project,
+                });
+            }
+        }
+    }
+    problems
+}
+
+/// Log all the problems, and fail if any of them is an error.
+pub fn check(
+    students: &[Student],
+    projects: &[Project],
+    problems: Vec<Problem>,
+) -> eyre::Result<()> {
+    let problems = validate(students, projects, problems);
+    for problem in &problems {
+        if problem.is_error() {
+            error!(%problem, "error found in data");
+        } else {
+            warn!(%problem, "problem found in data");
+        }
+    }
+    let errors = problems.iter().filter(|p| p.is_error()).count();
+    if errors > 0 {
+        bail!(
+            "{errors} error{s} found in data",
+            s = if errors == 1 { "" } else { "s" }
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_validate() {
+    use crate::testing;
+    let project = |id, min, max, occ| Project {
+        max_occurrences: occ,
+        ..testing::project(id, &format!("Project {id}"), min, max)
+    };
+    let projects = vec![
+        project(1, 2, 4, 1),
+        project(2, 5, 3, 1),
+        project(3, 1, 2, 0),
+        project(1, 2, 4, 1),
+    ];
+    let students = vec![
+        Student {
+            rankings: vec![vec![ProjectId(1)], vec![ProjectId(4), ProjectId(1)]],
+            bonuses: HashMap::from([(ProjectId(5), 1000)]),
+            ..testing::student(10, "Ada", "Lovelace", &[])
+        },
+        Student {
+            login: Some("aturing".into()),
+            ..testing::student(11, "Alan", "Turing", &[2])
+        },
+    ];
+    let raw = vec![Problem::UnknownPreferenceStudent {
+        student: StudentId(12),
+        project: ProjectId(1),
+    }];
+    let problems = validate(&students, &projects, raw.clone());
+    assert_eq!(
+        problems,
+        vec![
+            raw[0].clone(),
+            Problem::InvalidQuota {
+                project: ProjectId(2),
+                min: 5,
+                max: 3
+            },
+            Problem::NoOccurrences(ProjectId(3)),
+            Problem::DuplicateProject(ProjectId(1)),
+            Problem::UnknownRankedProject {
+                student: StudentId(10),
+                project: ProjectId(4)
+            },
+            Problem::DuplicateRanking {
+                student: StudentId(10),
+                project: ProjectId(1)
+            },
+            Problem::UnknownBonusProject {
+                student: StudentId(10),
+                project: ProjectId(5)
+            },
+        ]
+    );
+    assert!(validate(&students[1..], &[project(2, 1, 3, 1)], vec![]).is_empty());
+    let twice = vec![students[1].clone(), students[1].clone()];
+    assert_eq!(
+        validate(&twice, &[project(2, 1, 3, 1)], vec![]),
+        vec![
+            Problem::DuplicateStudent(StudentId(11)),
+            Problem::DuplicateLogin("aturing".into())
+        ]
+    );
+}
@@ -3,6 +3,8 @@
 
 MP="-h 127.0.0.1 -u root"
 
+cd "$(dirname "$0")"
+
 mysql $MP << __EOF__
 DROP DATABASE IF EXISTS paf_choix;
 DROP USER IF EXISTS paf_user;
@@ -11,4 +13,5 @@ CREATE USER paf_user IDENTIFIED BY 'paf_user';
 GRANT ALL ON paf_choix.* TO paf_user;
 CONNECT paf_choix;
 $(cat mysql.dump)
+$(cat mysql-history.sql)
 __EOF__
@@ -0,0 +1,20 @@
+#! /bin/sh
+#
+# Create the paf_choix database on a local PostgreSQL instance, and load
+# the data from mysql.dump into it.
+
+PP="-h 127.0.0.1 -U postgres"
+
+cd "$(dirname "$0")"
+
+psql $PP -q << __EOF__
+DROP DATABASE IF EXISTS paf_choix;
+DROP USER IF EXISTS paf_user;
+CREATE USER paf_user PASSWORD 'paf_user';
+CREATE DATABASE paf_choix OWNER paf_user;
+__EOF__
+
+(
+  cat postgres-schema.sql
+  grep '^INSERT INTO' mysql.dump | sed -e 's/`//g' -e "s/\\\\'/''/g"
+) | PGPASSWORD=paf_user psql -h 127.0.0.1 -U paf_user -q -v ON_ERROR_STOP=1 paf_choix
@@ -0,0 +1,18 @@
+DROP TABLE IF EXISTS `historique`;
+CREATE TABLE `historique` (
+  `id` int(11) NOT NULL AUTO_INCREMENT,
+  `date` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `campagne` tinytext DEFAULT NULL,
+  `config` text NOT NULL,
+  `stats` text NOT NULL,
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8;
+
+DROP TABLE IF EXISTS `historique_attributions`;
+CREATE TABLE `historique_attributions` (
+  `historique_id` int(11) NOT NULL,
+  `eleve_id` int(11) NOT NULL,
+  `projet_id` int(11) DEFAULT NULL,
+  `rang` int(11) DEFAULT NULL,
+  PRIMARY KEY (`historique_id`, `eleve_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8;
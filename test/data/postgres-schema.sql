@@ -0,0 +1,58 @@
+-- PostgreSQL version of the tables found in mysql.dump
+
+DROP TABLE IF EXISTS eleves;
+CREATE TABLE eleves (
+  id integer NOT NULL PRIMARY KEY,
+  login text NOT NULL,
+  prenom text NOT NULL,
+  nom text NOT NULL,
+  attribution integer DEFAULT NULL,
+  email varchar(80) DEFAULT NULL
+);
+
+DROP TABLE IF EXISTS pref_override;
+CREATE TABLE pref_override (
+  id serial PRIMARY KEY,
+  eleve_id integer NOT NULL,
+  projet_id integer NOT NULL,
+  poids integer NOT NULL,
+  date timestamp DEFAULT NULL
+);
+CREATE INDEX idx_eleve_proj ON pref_override (eleve_id, projet_id);
+
+DROP TABLE IF EXISTS preferences;
+CREATE TABLE preferences (
+  eleve_id integer NOT NULL DEFAULT 0,
+  projet_id integer NOT NULL DEFAULT 0,
+  poids integer NOT NULL DEFAULT 0,
+  date timestamp NOT NULL DEFAULT '2000-10-10 10:00:00',
+  index serial PRIMARY KEY
+);
+
+DROP TABLE IF EXISTS projets;
+CREATE TABLE projets (
+  id integer NOT NULL PRIMARY KEY,
+  intitule text NOT NULL,
+  quota_min integer NOT NULL,
+  quota_max integer NOT NULL,
+  occurrences integer NOT NULL,
+  url text
+);
+
+DROP TABLE IF EXISTS historique;
+CREATE TABLE historique (
+  id serial PRIMARY KEY,
+  date timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  campagne text DEFAULT NULL,
+  config text NOT NULL,
+  stats text NOT NULL
+);
+
+DROP TABLE IF EXISTS historique_attributions;
+CREATE TABLE historique_attributions (
+  historique_id integer NOT NULL,
+  eleve_id integer NOT NULL,
+  projet_id integer DEFAULT NULL,
+  rang integer DEFAULT NULL,
+  PRIMARY KEY (historique_id, eleve_id)
+);